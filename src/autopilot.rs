@@ -0,0 +1,394 @@
+use crate::bot::Bot;
+use crate::game::{Block, Direction, Field, Game, Position};
+use std::collections::VecDeque;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+/// For every block of the field, after how many steps it is free to move into.
+///
+/// Snake bodies free up from the tail: the block `k` segments from the end of
+/// a snake is left behind after `k + 1` steps, as long as the snake does not
+/// grow. Walls never free up.
+struct Board<'a> {
+    field: &'a Field,
+    free_after: Vec<u32>,
+}
+
+impl<'a> Board<'a> {
+    fn new(game: &'a Game) -> Board<'a> {
+        let field = game.field();
+        let mut board = Board {
+            field,
+            free_after: vec![0; field.width() * field.height()],
+        };
+        for y in 0..field.height() {
+            for x in 0..field.width() {
+                let position = Position {
+                    x: x as isize,
+                    y: y as isize,
+                };
+                if field.get_position(position) == Block::Wall {
+                    board.free_after[y * field.width() + x] = u32::MAX;
+                }
+            }
+        }
+        for snake in game.snakes().iter().filter(|snake| snake.alive()) {
+            board.occupy(snake.body().iter().copied());
+        }
+        board
+    }
+
+    /// Marks `body`, head first, as a snake's body.
+    fn occupy(&mut self, body: impl DoubleEndedIterator<Item = Position>) {
+        for (from_tail, position) in body.rev().enumerate() {
+            let index = self.index(position);
+            self.free_after[index] = from_tail as u32 + 1;
+        }
+    }
+
+    /// Marks the blocks the other snakes' heads can move into next as taken
+    /// for the first step, so `player` does not run into them head-on.
+    fn avoid_heads(&mut self, game: &Game, player: usize) {
+        for (_, snake) in game
+            .snakes()
+            .iter()
+            .enumerate()
+            .filter(|&(other, snake)| other != player && snake.alive())
+        {
+            for direction in DIRECTIONS {
+                let next = self.field.neighbor(snake.head(), &direction);
+                if self.field.get_position(next) != Block::Wall {
+                    let index = self.index(next);
+                    self.free_after[index] = self.free_after[index].max(1);
+                }
+            }
+        }
+    }
+
+    fn index(&self, position: Position) -> usize {
+        position.y as usize * self.field.width() + position.x as usize
+    }
+
+    /// Whether the snake can be at `position` after `steps` steps.
+    fn enterable(&self, position: Position, steps: u32) -> bool {
+        self.field.get_position(position) != Block::Wall
+            && self.free_after[self.index(position)] < steps
+    }
+
+    /// Shortest path from `start` to `goal`, as the positions after every
+    /// step, never moving in direction `forbidden` first.
+    fn path(&self, start: Position, goal: Position, forbidden: Direction) -> Option<Vec<Position>> {
+        let mut previous: Vec<Option<Position>> = vec![None; self.free_after.len()];
+        // the head's block may free up along the way, but going back through
+        // it would cut the path short when it is traced back
+        previous[self.index(start)] = Some(start);
+        let mut queue = VecDeque::from([(start, 0)]);
+
+        while let Some((position, steps)) = queue.pop_front() {
+            for direction in DIRECTIONS {
+                if steps == 0 && direction == forbidden {
+                    continue;
+                }
+                let next = self.field.neighbor(position, &direction);
+                if !self.enterable(next, steps + 1) || previous[self.index(next)].is_some() {
+                    continue;
+                }
+                previous[self.index(next)] = Some(position);
+
+                if next == goal {
+                    let mut path = vec![next];
+                    let mut current = position;
+                    while current != start {
+                        path.push(current);
+                        current = previous[self.index(current)].unwrap();
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back((next, steps + 1));
+            }
+        }
+        None
+    }
+
+    /// Number of blocks reachable from `start`.
+    fn area(&self, start: Position) -> usize {
+        let mut seen = vec![false; self.free_after.len()];
+        let mut queue = VecDeque::from([(start, 1)]);
+        seen[self.index(start)] = true;
+        let mut area = 0;
+
+        while let Some((position, steps)) = queue.pop_front() {
+            area += 1;
+            for direction in DIRECTIONS {
+                let next = self.field.neighbor(position, &direction);
+                if self.enterable(next, steps + 1) && !seen[self.index(next)] {
+                    seen[self.index(next)] = true;
+                    queue.push_back((next, steps + 1));
+                }
+            }
+        }
+        area
+    }
+}
+
+fn direction_to(field: &Field, from: Position, to: Position) -> Direction {
+    DIRECTIONS
+        .into_iter()
+        .find(|direction| field.neighbor(from, direction) == to)
+        .unwrap()
+}
+
+/// Direction of a Hamiltonian cycle through every block of a `width` x
+/// `height` board without walls, or `None` if there is no such cycle.
+///
+/// The cycle runs along the top row to the right, snakes back and forth
+/// through all other columns and returns up the first column, which needs an
+/// even number of rows; boards with an even number of columns use the
+/// transposed cycle.
+fn cycle_direction(width: usize, height: usize, position: Position) -> Option<Direction> {
+    if width < 2 || height < 2 {
+        return None;
+    }
+    if height % 2 == 1 {
+        if width % 2 == 1 {
+            return None;
+        }
+        let transposed = Position {
+            x: position.y,
+            y: position.x,
+        };
+        return cycle_direction(height, width, transposed).map(|direction| match direction {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Up,
+        });
+    }
+
+    let (x, y) = (position.x as usize, position.y as usize);
+    Some(if x == 0 {
+        if y == 0 {
+            Direction::Right
+        } else {
+            Direction::Up
+        }
+    } else if y % 2 == 0 {
+        if x < width - 1 {
+            Direction::Right
+        } else {
+            Direction::Down
+        }
+    } else if x > 1 || y == height - 1 {
+        Direction::Left
+    } else {
+        Direction::Down
+    })
+}
+
+/// Built-in computer player.
+///
+/// Takes the shortest path to the food if the snake could still reach its
+/// own tail after eating it, otherwise follows its tail. When it cannot reach
+/// its tail either, or has gone half a board's worth of steps without eating,
+/// it follows a Hamiltonian cycle of the board, which eventually reaches all
+/// food; failing that it moves towards the most open space. Blocks other
+/// snakes' heads could move into next are only entered as a last resort.
+#[derive(Default)]
+pub struct Autopilot {
+    /// Length of the snake when the bot last steered it.
+    length: usize,
+    /// Tick the snake last grew on.
+    last_meal: u64,
+    /// Whether the snake stalled and now sticks to the cycle.
+    cycling: bool,
+}
+
+impl Autopilot {
+    /// Whether the snake can still reach its tail after following `path`,
+    /// having grown by a block at its end if it `eats` there.
+    fn reaches_tail(game: &Game, player: usize, path: &[Position], eats: bool) -> bool {
+        let snake = &game.snakes()[player];
+        let body: Vec<Position> = path
+            .iter()
+            .rev()
+            .chain(snake.body().iter())
+            .take(snake.length() + usize::from(eats))
+            .copied()
+            .collect();
+        if body.len() < 2 {
+            return true;
+        }
+
+        let mut board = Board::new(game);
+        let steps = path.len() as u32;
+        for free_after in board.free_after.iter_mut() {
+            if *free_after != u32::MAX {
+                *free_after = free_after.saturating_sub(steps);
+            }
+        }
+        board.occupy(body.iter().copied());
+
+        let head = body[0];
+        let tail = body[body.len() - 1];
+        let backwards = direction_to(game.field(), head, body[1]);
+        board.path(head, tail, backwards).is_some()
+    }
+
+    /// Whether moving to `next` keeps the snake safe, checking a move that
+    /// eats against the body as it will be after growing.
+    fn safe_step(game: &Game, player: usize, board: &Board, next: Position) -> bool {
+        let eats = game.field().food() == Some(next);
+        board.enterable(next, 1) && (!eats || Self::reaches_tail(game, player, &[next], true))
+    }
+
+    /// Whether following the cycle from `head` is safe until the snake's
+    /// whole body, as it is now, has been left behind. Food on the way makes
+    /// the tail wait a step.
+    fn cycle_is_safe(game: &Game, player: usize, board: &Board, head: Position) -> bool {
+        let field = game.field();
+        let length = game.snakes()[player].length() as u32;
+        let mut position = head;
+        let (mut steps, mut eaten) = (0, 0);
+        while steps - eaten <= length {
+            let Some(direction) = cycle_direction(field.width(), field.height(), position) else {
+                return false;
+            };
+            position = field.neighbor(position, &direction);
+            steps += 1;
+            if !board.enterable(position, steps - eaten) {
+                return false;
+            }
+            if field.food() == Some(position) {
+                eaten += 1;
+            }
+        }
+        true
+    }
+
+    /// Keeps track of when the snake last ate, starting over when it is
+    /// shorter than before, i.e. in a new game.
+    fn track(&mut self, game: &Game, player: usize) {
+        let length = game.snakes()[player].length();
+        if length < self.length || game.tick() < self.last_meal {
+            *self = Autopilot::default();
+        }
+        if length != self.length {
+            self.length = length;
+            self.last_meal = game.tick();
+        }
+        let field = game.field();
+        if game.tick() - self.last_meal > (field.width() * field.height() / 2) as u64 {
+            self.cycling = true;
+        }
+    }
+}
+
+impl Bot for Autopilot {
+    fn next_direction(&mut self, game: &Game, player: usize) -> Direction {
+        self.track(game, player);
+        let snake = &game.snakes()[player];
+        let field = game.field();
+        let head = snake.head();
+        let backwards = snake.direction().opposite();
+        let mut board = Board::new(game);
+        board.avoid_heads(game, player);
+
+        let has_walls = game
+            .config()
+            .level
+            .as_ref()
+            .is_some_and(|level| !level.walls.is_empty());
+        let cycle = cycle_direction(field.width(), field.height(), head)
+            .filter(|direction| !has_walls && *direction != backwards);
+        let safe_cycle = cycle.filter(|_| Self::cycle_is_safe(game, player, &board, head));
+        if self.cycling {
+            // a stalled snake gets its body onto the cycle bit by bit, as
+            // long as it can still reach its tail
+            if let Some(direction) = safe_cycle.or(cycle.filter(|direction| {
+                let next = field.neighbor(head, direction);
+                let eats = field.food() == Some(next);
+                board.enterable(next, 1) && Self::reaches_tail(game, player, &[next], eats)
+            })) {
+                return direction;
+            }
+        }
+
+        if let Some(food) = field.food() {
+            if let Some(path) = board.path(head, food, backwards) {
+                if Self::reaches_tail(game, player, &path, true) {
+                    return direction_to(field, head, path[0]);
+                }
+            }
+        }
+
+        let tail = snake.body()[snake.length() - 1];
+        if let Some(path) = board.path(head, tail, backwards) {
+            if Self::safe_step(game, player, &board, path[0]) {
+                return direction_to(field, head, path[0]);
+            }
+        }
+
+        if let Some(direction) = safe_cycle {
+            return direction;
+        }
+
+        let roomiest = |board: &Board| {
+            DIRECTIONS
+                .into_iter()
+                .filter(|direction| *direction != backwards)
+                .map(|direction| (direction, field.neighbor(head, &direction)))
+                .filter(|(_, next)| board.enterable(*next, 1))
+                .max_by_key(|(_, next)| board.area(*next))
+        };
+        // a block next to another head is still better than a certain crash
+        roomiest(&board)
+            .or_else(|| roomiest(&Board::new(game)))
+            .map_or(snake.direction(), |(direction, _)| direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+
+    #[test]
+    fn never_turns_back_into_its_neck() {
+        // the food starts right behind the snake on this board, and a path
+        // through the freed head block used to end in a reversal
+        let mut game = Game::new(&GameConfig::new(30, 20, 5));
+        let mut autopilot = Autopilot::default();
+        for _ in 0..50 {
+            let snake = &game.snakes()[0];
+            let direction = autopilot.next_direction(&game, 0);
+            assert!(direction != snake.direction().opposite());
+            game.step(&[Some(direction)]);
+        }
+        assert!(game.score() > 0);
+    }
+
+    #[test]
+    fn keeps_out_of_reach_of_other_heads() {
+        for seed in 0..20 {
+            let mut config = GameConfig::new(11, 5, seed);
+            config.players = 2;
+            let mut game = Game::new(&config);
+            // the snakes start at (2, 1) and (8, 3) moving towards each
+            // other, which leaves their heads two blocks apart in a column
+            for _ in 0..3 {
+                game.step(&[None, None]);
+            }
+            let (head, other) = (game.snakes()[0].head(), game.snakes()[1].head());
+            assert_eq!((head.x, head.y, other.x, other.y), (5, 1, 5, 3));
+
+            let direction = Autopilot::default().next_direction(&game, 0);
+            assert!(direction != Direction::Down, "seed {seed}");
+        }
+    }
+}
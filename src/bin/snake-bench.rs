@@ -0,0 +1,261 @@
+use snake::autopilot::Autopilot;
+use snake::bot::{self, Bot};
+use snake::cli::{value, MIN_HEIGHT, MIN_WIDTH};
+use snake::external::{self, ExternalBot};
+use snake::game::Death;
+use snake::level::Level;
+use snake::net::MAX_PLAYERS;
+use snake::*;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::process::exit;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: snake-bench [OPTIONS]
+
+Plays many seeded games with a bot and prints statistics about the first
+player's snake. Any other snakes are steered by the autopilot.
+
+Options:
+  --games <count>     number of games to play (default: 100)
+  --seed <number>     seed of the first game, the others count up from it (default: 0)
+  --width <blocks>    board width (default: 30)
+  --height <blocks>   board height (default: 20)
+  --wrap              leaving the board on one side enters it on the opposite one
+  --players <count>   number of snakes on the board, all steered by bots (default: 1)
+  --level <name|file> play a level file or one of the bundled levels
+  --bot <command>     steer the first snake with an external program instead of
+                      the autopilot, see `snake --help`
+  --bot-time <ms>     time the --bot program gets to answer every step (default: 100)
+  --json              print the statistics as JSON
+  --help              print this help";
+
+struct Options {
+    games: u64,
+    seed: u64,
+    config: GameConfig,
+    bot: Option<String>,
+    budget: Duration,
+    json: bool,
+}
+
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.into_iter();
+    let mut options = Options {
+        games: 100,
+        seed: 0,
+        config: GameConfig::new(30, 20, 0),
+        bot: None,
+        budget: external::DEFAULT_BUDGET,
+        json: false,
+    };
+    let mut sized = false;
+
+    while let Some(arg) = args.next() {
+        let config = &mut options.config;
+        match arg.as_str() {
+            "--games" => options.games = value(&arg, &mut args)?,
+            "--seed" => options.seed = value(&arg, &mut args)?,
+            "--width" => {
+                config.width = value(&arg, &mut args)?;
+                sized = true;
+            }
+            "--height" => {
+                config.height = value(&arg, &mut args)?;
+                sized = true;
+            }
+            "--wrap" => config.wrap = true,
+            "--players" => config.players = value(&arg, &mut args)?,
+            "--level" => {
+                let level = Level::load(&value::<String>(&arg, &mut args)?)?;
+                config.width = level.width;
+                config.height = level.height;
+                config.level = Some(level);
+            }
+            "--bot" => options.bot = Some(value(&arg, &mut args)?),
+            "--bot-time" => options.budget = Duration::from_millis(value(&arg, &mut args)?),
+            "--json" => options.json = true,
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    let config = &options.config;
+    if sized && config.level.is_some() {
+        return Err("--width and --height cannot be used with --level".to_string());
+    }
+    if config.width < MIN_WIDTH || config.height < MIN_HEIGHT {
+        return Err(format!(
+            "the board must be at least {MIN_WIDTH}x{MIN_HEIGHT}"
+        ));
+    }
+    if !(1..=MAX_PLAYERS).contains(&config.players) {
+        return Err(format!("--players must be between 1 and {MAX_PLAYERS}"));
+    }
+    if config.players > 1 && config.level.is_some() {
+        return Err("levels are single-player".to_string());
+    }
+    Ok(Some(options))
+}
+
+/// Results of all games, from the first player's point of view.
+#[derive(Default)]
+struct Stats {
+    lengths: Vec<usize>,
+    steps: u64,
+    food: u64,
+    completed: usize,
+    survived: usize,
+    stalled: usize,
+    bot_errors: usize,
+    wall: usize,
+    self_collision: usize,
+    opponent: usize,
+}
+
+impl Stats {
+    fn add(&mut self, game: &Game, outcome: Outcome, bot: &dyn Bot) {
+        let snake = &game.snakes()[0];
+        self.bot_errors += bot.errors().len();
+        self.lengths.push(snake.length());
+        self.steps += game.tick();
+        self.food += snake.score() as u64;
+
+        match (outcome, snake.death()) {
+            (Outcome::Won, _) => self.completed += 1,
+            (Outcome::Quit, _) => self.stalled += 1,
+            (Outcome::Lost, None) => self.survived += 1,
+            (Outcome::Lost, Some(Death::Wall)) => self.wall += 1,
+            (Outcome::Lost, Some(Death::SelfCollision)) => self.self_collision += 1,
+            (Outcome::Lost, Some(Death::Opponent)) => self.opponent += 1,
+            (Outcome::Lost, Some(Death::Left)) => unreachable!("bots never leave"),
+            (Outcome::Menu, _) => unreachable!("bots have no menu"),
+        }
+    }
+
+    fn games(&self) -> usize {
+        self.lengths.len()
+    }
+
+    fn rate(&self, count: usize) -> f64 {
+        count as f64 / self.games().max(1) as f64
+    }
+
+    fn mean_length(&self) -> f64 {
+        self.lengths.iter().sum::<usize>() as f64 / self.games().max(1) as f64
+    }
+
+    fn median_length(&self) -> f64 {
+        let mut lengths = self.lengths.clone();
+        lengths.sort_unstable();
+        match lengths.len() {
+            0 => 0.,
+            n if n % 2 == 1 => lengths[n / 2] as f64,
+            n => (lengths[n / 2 - 1] + lengths[n / 2]) as f64 / 2.,
+        }
+    }
+
+    fn max_length(&self) -> usize {
+        self.lengths.iter().copied().max().unwrap_or(0)
+    }
+
+    fn steps_per_food(&self) -> f64 {
+        self.steps as f64 / self.food.max(1) as f64
+    }
+
+    fn table(&self) -> String {
+        let percent = |count| format!("{:.1}%", self.rate(count) * 100.);
+        [
+            ("games", self.games().to_string()),
+            ("mean length", format!("{:.1}", self.mean_length())),
+            ("median length", format!("{:.1}", self.median_length())),
+            ("max length", self.max_length().to_string()),
+            ("steps per food", format!("{:.1}", self.steps_per_food())),
+            ("completed", percent(self.completed)),
+            ("survived", percent(self.survived)),
+            ("stalled", percent(self.stalled)),
+            ("bot errors", self.bot_errors.to_string()),
+            ("died on wall", percent(self.wall)),
+            ("died on self", percent(self.self_collision)),
+            ("died on opponent", percent(self.opponent)),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{name:<18}{value:>10}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    fn json(&self) -> String {
+        format!(
+            concat!(
+                "{{\"games\":{},\"mean_length\":{:.3},\"median_length\":{:.1},",
+                "\"max_length\":{},\"steps_per_food\":{:.3},\"completion_rate\":{:.4},",
+                "\"survival_rate\":{:.4},\"stall_rate\":{:.4},\"bot_errors\":{},",
+                "\"deaths\":{{\"wall\":{:.4},\"self\":{:.4},\"opponent\":{:.4}}}}}"
+            ),
+            self.games(),
+            self.mean_length(),
+            self.median_length(),
+            self.max_length(),
+            self.steps_per_food(),
+            self.rate(self.completed),
+            self.rate(self.survived),
+            self.rate(self.stalled),
+            self.bot_errors,
+            self.rate(self.wall),
+            self.rate(self.self_collision),
+            self.rate(self.opponent),
+        )
+    }
+}
+
+fn main() {
+    let options = match parse(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            exit(2);
+        }
+    };
+
+    let mut stats = Stats::default();
+    let progress = io::stderr().is_terminal();
+    for game in 0..options.games {
+        if progress {
+            eprint!("\rgame {}/{}", game + 1, options.games);
+        }
+        let config = GameConfig {
+            seed: options.seed.wrapping_add(game),
+            ..options.config.clone()
+        };
+        let mut game = Game::new(&config);
+        let mut bots: Vec<Box<dyn Bot>> = (0..config.players)
+            .map(|_| Box::<Autopilot>::default() as Box<dyn Bot>)
+            .collect();
+        if let Some(command) = &options.bot {
+            match ExternalBot::spawn(command, options.budget, false) {
+                Ok(bot) => bots[0] = Box::new(bot),
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    exit(2);
+                }
+            }
+        }
+        let outcome = bot::play(&mut game, &mut bots, bot::patience(&config));
+        stats.add(&game, outcome, bots[0].as_ref());
+    }
+    if progress {
+        eprint!("\r\x1b[K");
+    }
+
+    if options.json {
+        println!("{}", stats.json());
+    } else {
+        println!("{}", stats.table());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::error::Error;
+use crate::frontend::{Input, InputSource, MenuInput, Outcome};
+use crate::game::{Direction, Game, GameConfig, StepResult};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A computer player.
+pub trait Bot {
+    /// Direction `player`'s snake should move in on the next step.
+    fn next_direction(&mut self, game: &Game, player: usize) -> Direction;
+
+    /// Problems the bot ran into so far, e.g. invalid replies from an
+    /// external program.
+    fn errors(&self) -> &[BotError] {
+        &[]
+    }
+}
+
+impl<B: Bot + ?Sized> Bot for Box<B> {
+    fn next_direction(&mut self, game: &Game, player: usize) -> Direction {
+        (**self).next_direction(game, player)
+    }
+
+    fn errors(&self) -> &[BotError] {
+        (**self).errors()
+    }
+}
+
+/// Something that went wrong while a bot was deciding on a step.
+#[derive(Clone)]
+pub struct BotError {
+    pub tick: u64,
+    pub message: String,
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tick {}: {}", self.tick, self.message)
+    }
+}
+
+/// Lets a bot steer one snake inside [`crate::run`].
+///
+/// The wrapped source is still read, so the viewer can pause or quit and the
+/// other players can steer their own snakes; turns for the bot's snake are
+/// ignored.
+pub struct BotInput<B: Bot, I: InputSource> {
+    bot: B,
+    player: usize,
+    inner: I,
+    decided: Option<u64>,
+}
+
+impl<B: Bot, I: InputSource> BotInput<B, I> {
+    pub fn new(bot: B, player: usize, inner: I) -> BotInput<B, I> {
+        BotInput {
+            bot,
+            player,
+            inner,
+            decided: None,
+        }
+    }
+
+    pub fn bot(&self) -> &B {
+        &self.bot
+    }
+}
+
+impl<B: Bot, I: InputSource> InputSource for BotInput<B, I> {
+    fn poll(&mut self, game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.inner.poll(game, remaining)? {
+                Some(Input::Turn(player, _)) if player == self.player => (),
+                Some(input) => return Ok(Some(input)),
+                None => break,
+            }
+        }
+
+        if self.decided == Some(game.tick()) {
+            return Ok(None);
+        }
+        self.decided = Some(game.tick());
+        let direction = self.bot.next_direction(game, self.player);
+        Ok(Some(Input::Turn(self.player, direction)))
+    }
+
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        self.inner.wait_for_menu()
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        self.inner.wait_for_key()
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        self.inner.poll_key(timeout)
+    }
+}
+
+/// Steps without any snake eating after which a game played by bots is
+/// considered stuck.
+pub fn patience(config: &GameConfig) -> u64 {
+    (config.width * config.height * 2) as u64
+}
+
+/// Plays `game` without a frontend, with `bots[i]` steering player `i`'s snake.
+///
+/// Gives up with [`Outcome::Quit`] once no snake has eaten for `patience`
+/// steps, so bots that circle forever still finish.
+pub fn play(game: &mut Game, bots: &mut [Box<dyn Bot>], patience: u64) -> Outcome {
+    let mut last_meal = 0;
+    loop {
+        let turns: Vec<Option<Direction>> = bots
+            .iter_mut()
+            .enumerate()
+            .map(|(player, bot)| {
+                let alive = game.snakes().get(player).is_some_and(|snake| snake.alive());
+                alive.then(|| bot.next_direction(game, player))
+            })
+            .collect();
+
+        match game.step(&turns) {
+            StepResult::Won => return Outcome::Won,
+            StepResult::Lost => return Outcome::Lost,
+            StepResult::Ate => last_meal = game.tick(),
+            StepResult::Moved if game.tick() - last_meal > patience => return Outcome::Quit,
+            StepResult::Moved => (),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::error::Error;
+use crate::frontend::{run, InputSource, Outcome, Renderer, CAMPAIGN_MENU};
+use crate::game::{Game, GameConfig};
+use crate::level::{Level, BUNDLED};
+use crate::paths;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const HEADER: &str = "snake-campaign 1";
+
+/// Lives the player starts the campaign with.
+pub const LIVES: u32 = 3;
+
+/// Campaign progress kept between runs.
+#[derive(Default)]
+pub struct Progress {
+    /// Index of the furthest level the player has reached.
+    pub unlocked: usize,
+}
+
+impl Progress {
+    fn path() -> Option<PathBuf> {
+        paths::data_dir().map(|dir| dir.join("campaign"))
+    }
+
+    /// Reads the saved progress; a missing file means a fresh start.
+    pub fn load() -> Result<Progress, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Progress::default());
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Ok(Progress::default());
+        };
+
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(format!("{} is not a campaign save", path.display()));
+        }
+        let unlocked = lines
+            .next()
+            .and_then(|line| line.strip_prefix("unlocked "))
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("{}: invalid unlocked level", path.display()))?;
+
+        Ok(Progress { unlocked })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("cannot find a data directory, HOME is not set")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {e}", dir.display()))?;
+        }
+        fs::write(&path, format!("{HEADER}\nunlocked {}\n", self.unlocked))
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+}
+
+/// A run through the bundled levels, advancing whenever the snake reaches
+/// the level's target length.
+pub struct Campaign {
+    levels: Vec<Level>,
+    level: usize,
+    score: usize,
+    lives: u32,
+    /// Settings every level is played with, apart from its board.
+    rules: GameConfig,
+    /// Initial step time that overrides the levels' own.
+    speed: Option<Duration>,
+    games: u64,
+}
+
+impl Campaign {
+    /// Starts the bundled campaign at level index `start`, playing by `rules`
+    /// with the levels' own step time unless `speed` is given; every game's
+    /// seed is derived from the rules' seed.
+    pub fn bundled(
+        start: usize,
+        rules: GameConfig,
+        speed: Option<Duration>,
+    ) -> Result<Campaign, String> {
+        let levels = BUNDLED
+            .iter()
+            .map(|(name, _)| Level::load(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(level) = levels.iter().find(|level| level.target.is_none()) {
+            return Err(format!("campaign level {} has no target", level.name));
+        }
+
+        Ok(Campaign {
+            level: start.min(levels.len() - 1),
+            levels,
+            score: 0,
+            lives: LIVES,
+            rules,
+            speed,
+            games: 0,
+        })
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    /// Number of the current level, starting at 1.
+    pub fn level(&self) -> usize {
+        self.level + 1
+    }
+
+    /// Food eaten over all games of the campaign.
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    fn config(&self) -> GameConfig {
+        let level = &self.levels[self.level];
+        GameConfig {
+            width: level.width,
+            height: level.height,
+            speed: self.speed.or(level.speed).unwrap_or(self.rules.speed),
+            seed: self.rules.seed.wrapping_add(self.games),
+            level: Some(level.clone()),
+            ..self.rules.clone()
+        }
+    }
+
+    fn introduce(&self, headline: &str) -> String {
+        let level = &self.levels[self.level];
+        format!(
+            "{headline}\n\nLevel {}/{}: {}\nReach length {} to advance.\n\nLives: {}  Score: {}\n\nPress any key to continue.",
+            self.level(),
+            self.levels.len(),
+            level.name,
+            level.target.unwrap_or_default(),
+            self.lives,
+            self.score
+        )
+    }
+
+    /// Plays levels until the campaign is completed, the player runs out of
+    /// lives or quits, saving every newly unlocked level to `progress`.
+    pub fn play(
+        &mut self,
+        renderer: &mut impl Renderer,
+        input: &mut impl InputSource,
+        progress: &mut Progress,
+    ) -> Result<Outcome, Error> {
+        let mut headline = "Campaign".to_string();
+        loop {
+            renderer.show_message(&self.introduce(&headline))?;
+            if !input.wait_for_key()? {
+                return Ok(Outcome::Quit);
+            }
+
+            let mut game = Game::new(&self.config());
+            self.games += 1;
+            let outcome = run(&mut game, renderer, input, CAMPAIGN_MENU)?;
+            if outcome != Outcome::Menu {
+                self.score += game.score();
+            }
+
+            match outcome {
+                Outcome::Won if self.level + 1 == self.levels.len() => return Ok(Outcome::Won),
+                Outcome::Won => {
+                    self.level += 1;
+                    headline = "Level complete!".to_string();
+                    if self.level > progress.unlocked {
+                        progress.unlocked = self.level;
+                        if let Err(message) = progress.save() {
+                            headline += &format!("\n(progress not saved: {message})");
+                        }
+                    }
+                }
+                Outcome::Lost => {
+                    self.lives -= 1;
+                    if self.lives == 0 {
+                        return Ok(Outcome::Lost);
+                    }
+                    headline = "You crashed!".to_string();
+                }
+                // leaving a level costs no life, it can just be started again
+                Outcome::Menu => headline = "Campaign".to_string(),
+                Outcome::Quit => return Ok(Outcome::Quit),
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::external::DEFAULT_BUDGET;
+use crate::game::GameConfig;
+use crate::keys::Keys;
+use crate::level::Level;
+use crate::speed::{self, SpeedCurve};
+use crate::theme::{self, Theme};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: snake [OPTIONS]
+
+Options:
+  --width <blocks>        board width (default: fill the terminal)
+  --height <blocks>       board height (default: fill the terminal)
+  --speed <ms>            initial time between two steps (default: the difficulty's)
+  --difficulty <name>     how fast the snake starts and speeds up: easy, normal, hard
+                          or insane (default: normal)
+  --speed-curve <curve>   how the step time changes: constant, \"exponential <factor>\",
+                          \"per-food <ms> <min ms>\" or \"table <length>:<ms>...\"
+                          (default: the difficulty's)
+  --acceleration <factor> the same as --speed-curve \"exponential <factor>\"
+  --wrap                  leaving the board on one side enters it on the opposite one
+  --two-player            two snakes on one board, by default player 1 on WASD and player 2 on the arrows
+  --one-player            a single snake, overriding a 2-player mode in the config file
+  --level <name|file>     play a level file or one of the bundled levels:
+                          pillars, cross, rooms, corridors
+  --seed <number>         seed for food placement (default: random)
+  --autopilot             let the computer steer the snake, or player 2's snake with --two-player
+  --bot <command>         like --autopilot, but steered by an external program that reads
+                          the board as JSON lines on stdin and answers with directions
+  --bot-time <ms>         time the --bot program gets to answer every step (default: 100)
+  --headless              with --autopilot or --bot, play without a terminal and print the result
+  --host <port>           host a network game on <port>, which starts once every player is ready;
+                          with --headless, stream a match between bots to spectators instead
+  --join <address>        join a network game, e.g. --join localhost:4000
+  --spectate <address>    watch a network game or bot match, the arrows highlight another snake
+  --campaign              play the bundled levels in order, resuming at the last one reached
+  --record <file>         save the game's inputs to <file> so it can be replayed
+  --replay <file>         watch a game recorded with --record
+  --name <name>           name to put in the high-score table (default: $USER)
+  --theme <name>          look of the board: classic, neon, high-contrast, colorblind,
+                          or ascii for terminals without Unicode (default: classic)
+  --monochrome            draw without colors, also the default when NO_COLOR is set
+  --scores                print the high-score tables
+  --print-config          print the settings in effect, in the format of the config file
+  --help                  print this help
+
+Settings, including key bindings, are read from $XDG_CONFIG_HOME/clisnake/config.ini
+(~/.config/clisnake/config.ini by default); the options above override them.";
+
+/// Smallest board the border and score still fit around.
+pub const MIN_WIDTH: usize = 10;
+pub const MIN_HEIGHT: usize = 5;
+
+/// What the player asked for on the command line.
+pub enum Command {
+    Play(Options),
+    Campaign(Options),
+    Replay(String, Options),
+    /// Join the network game hosted at the given address.
+    Join(String, Options),
+    /// Watch the network game hosted at the given address.
+    Spectate(String, Options),
+    /// Print the effective settings as a config file.
+    PrintConfig(Options),
+    Scores,
+    Help,
+}
+
+pub struct Options {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    /// Initial step time in milliseconds, or the level's or difficulty's
+    /// speed if unset.
+    pub speed: Option<u64>,
+    /// Name of the difficulty preset, which gives the speed and speed curve
+    /// unless they are set on their own.
+    pub difficulty: String,
+    pub curve: Option<SpeedCurve>,
+    pub wrap: bool,
+    pub players: usize,
+    pub level: Option<String>,
+    pub seed: Option<u64>,
+    pub record: Option<String>,
+    pub autopilot: bool,
+    /// Command starting an external bot.
+    pub bot: Option<String>,
+    /// Milliseconds the external bot gets for every step.
+    pub bot_time: u64,
+    pub headless: bool,
+    /// Port to host a network game on.
+    pub host: Option<u16>,
+    pub name: Option<String>,
+    pub theme: &'static Theme,
+    /// Whether to draw in the theme's colors.
+    pub color: bool,
+    pub keys: Keys,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width: None,
+            height: None,
+            speed: None,
+            difficulty: "normal".to_string(),
+            curve: None,
+            wrap: false,
+            players: 1,
+            level: None,
+            seed: None,
+            record: None,
+            autopilot: false,
+            bot: None,
+            bot_time: DEFAULT_BUDGET.as_millis() as u64,
+            headless: false,
+            host: None,
+            name: None,
+            theme: &theme::CLASSIC,
+            color: !theme::no_color(),
+            keys: Keys::default(),
+        }
+    }
+}
+
+/// Parses the value following `flag`.
+pub fn value<T: FromStr>(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or_else(|| format!("{flag} requires a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {flag}: {value}"))
+}
+
+/// Parses the program arguments, without the program name, on top of
+/// `options` read from the config file.
+pub fn parse(
+    args: impl IntoIterator<Item = String>,
+    mut options: Options,
+) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut campaign = false;
+    let mut print_config = false;
+    let mut command: Option<fn(String, Options) -> Command> = None;
+    let mut target = String::new();
+    // the config file's size and mode only apply where they can, so these
+    // remember what was given on the command line
+    let mut sized = false;
+    let mut players = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => {
+                options.width = Some(value(&arg, &mut args)?);
+                sized = true;
+            }
+            "--height" => {
+                options.height = Some(value(&arg, &mut args)?);
+                sized = true;
+            }
+            "--speed" => options.speed = Some(value(&arg, &mut args)?),
+            "--difficulty" => {
+                let name: String = value(&arg, &mut args)?;
+                speed::difficulty(&name)?;
+                options.difficulty = name;
+            }
+            "--speed-curve" => options.curve = Some(value::<String>(&arg, &mut args)?.parse()?),
+            "--acceleration" => {
+                let factor: f64 = value(&arg, &mut args)?;
+                if !(factor > 0. && factor <= 1.) {
+                    return Err("--acceleration must be in (0, 1]".to_string());
+                }
+                options.curve = Some(SpeedCurve::Exponential(factor));
+            }
+            "--wrap" => options.wrap = true,
+            "--two-player" => players = Some(2),
+            "--one-player" => players = Some(1),
+            "--level" => options.level = Some(value(&arg, &mut args)?),
+            "--host" => options.host = Some(value(&arg, &mut args)?),
+            "--join" => {
+                target = value(&arg, &mut args)?;
+                command = Some(Command::Join);
+            }
+            "--spectate" => {
+                target = value(&arg, &mut args)?;
+                command = Some(Command::Spectate);
+            }
+            "--campaign" => campaign = true,
+            "--autopilot" => options.autopilot = true,
+            "--bot" => options.bot = Some(value(&arg, &mut args)?),
+            "--bot-time" => options.bot_time = value(&arg, &mut args)?,
+            "--headless" => options.headless = true,
+            "--seed" => options.seed = Some(value(&arg, &mut args)?),
+            "--record" => options.record = Some(value(&arg, &mut args)?),
+            "--replay" => {
+                target = value(&arg, &mut args)?;
+                command = Some(Command::Replay);
+            }
+            "--name" => options.name = Some(value(&arg, &mut args)?),
+            "--theme" => options.theme = theme::find(&value::<String>(&arg, &mut args)?)?,
+            "--monochrome" => options.color = false,
+            "--print-config" => print_config = true,
+            "--scores" => return Ok(Command::Scores),
+            "--help" | "-h" => return Ok(Command::Help),
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    if let Some(command) = command {
+        return Ok(command(target, options));
+    }
+    if options.speed == Some(0) {
+        return Err("--speed must be at least 1 ms".to_string());
+    }
+    if options.level.is_some() && sized {
+        return Err("--width and --height cannot be used with --level".to_string());
+    }
+    let levels = campaign || options.level.is_some();
+    if levels && !sized {
+        options.width = None;
+        options.height = None;
+    }
+    match players {
+        Some(players) => options.players = players,
+        None if levels || (options.host.is_some() && !options.headless) => options.players = 1,
+        None => (),
+    }
+
+    if campaign && options.level.is_some() {
+        return Err("--level cannot be used with --campaign".to_string());
+    }
+    if options.players > 1 && (campaign || options.level.is_some()) {
+        return Err("--two-player cannot be used with levels".to_string());
+    }
+    if options.autopilot && options.bot.is_some() {
+        return Err("--autopilot and --bot cannot be used together".to_string());
+    }
+    let bot = options.autopilot || options.bot.is_some();
+    if options.headless && !bot {
+        return Err("--headless needs --autopilot or --bot".to_string());
+    }
+    if campaign && bot {
+        return Err("--autopilot and --bot cannot be used with --campaign".to_string());
+    }
+    if options.host.is_some() && (campaign || options.level.is_some()) {
+        return Err("--host cannot be used with levels".to_string());
+    }
+    if options.host.is_some() && options.players > 1 && !options.headless {
+        return Err("--host needs --headless to be used with --two-player".to_string());
+    }
+    if print_config {
+        Ok(Command::PrintConfig(options))
+    } else if campaign {
+        Ok(Command::Campaign(options))
+    } else {
+        Ok(Command::Play(options))
+    }
+}
+
+impl Options {
+    /// Builds the game configuration for a terminal that fits at most
+    /// `max_width` x `max_height` blocks.
+    pub fn into_config(self, max_width: usize, max_height: usize) -> Result<GameConfig, String> {
+        let level = self.level.as_deref().map(Level::load).transpose()?;
+        let (width, height) = match &level {
+            Some(level) => (level.width, level.height),
+            None => (
+                self.width.unwrap_or(max_width),
+                self.height.unwrap_or(max_height),
+            ),
+        };
+
+        if width > max_width || height > max_height {
+            return Err(format!(
+                "a {width}x{height} board does not fit the terminal (at most {max_width}x{max_height})"
+            ));
+        }
+        if width < MIN_WIDTH || height < MIN_HEIGHT {
+            return Err(format!(
+                "a {width}x{height} board is too small (at least {MIN_WIDTH}x{MIN_HEIGHT})"
+            ));
+        }
+
+        let (difficulty_speed, difficulty_curve) = speed::difficulty(&self.difficulty)?;
+        let speed = match (self.speed, &level) {
+            (Some(ms), _) => Duration::from_millis(ms),
+            (None, Some(level)) => level.speed.unwrap_or(difficulty_speed),
+            (None, None) => difficulty_speed,
+        };
+
+        Ok(GameConfig {
+            width,
+            height,
+            speed,
+            curve: self.curve.unwrap_or(difficulty_curve),
+            wrap: self.wrap,
+            players: self.players,
+            seed: self.seed.unwrap_or_else(rand::random),
+            level,
+        })
+    }
+}
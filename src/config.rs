@@ -0,0 +1,155 @@
+use crate::cli::Options;
+use crate::game::GameConfig;
+use crate::keys::ACTIONS;
+use crate::paths;
+use crate::speed;
+use crate::theme;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Modes the config file can pick, as `(name, players, wrap)`, named like
+/// [`GameConfig::mode`] names them.
+const MODES: [(&str, usize, bool); 4] = [
+    ("classic", 1, false),
+    ("wrap", 1, true),
+    ("2-player", 2, false),
+    ("2-player wrap", 2, true),
+];
+
+pub fn path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("config.ini"))
+}
+
+/// Applies the config file to `options`; a missing file changes nothing.
+pub fn load(options: &mut Options) -> Result<(), String> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    apply(&text, options).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {key}: {value}"))
+}
+
+/// Applies the settings in `text` to `options`.
+///
+/// The text is INI-style: `key = value` lines in `[game]`, `[display]` and
+/// `[keys]` sections, where `#` starts a comment line. The `[keys]` section
+/// binds each action in [`ACTIONS`] to a space-separated list of keys.
+pub fn apply(text: &str, options: &mut Options) -> Result<(), String> {
+    let mut section = String::new();
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name.trim().to_string();
+            if !["game", "display", "keys"].contains(&section.as_str()) {
+                return Err(format!("line {number}: unknown section [{section}]"));
+            }
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {number}: expected key = value"))?;
+        let (key, value) = (key.trim(), value.trim());
+        let result = match (section.as_str(), key) {
+            ("game", "width") => parse(key, value).map(|width| options.width = Some(width)),
+            ("game", "height") => parse(key, value).map(|height| options.height = Some(height)),
+            ("game", "speed") => parse(key, value).map(|speed| options.speed = Some(speed)),
+            ("game", "difficulty") => {
+                speed::difficulty(value).map(|_| options.difficulty = value.to_string())
+            }
+            ("game", "curve") => value.parse().map(|curve| options.curve = Some(curve)),
+            ("game", "mode") => match MODES.iter().find(|(name, ..)| *name == value) {
+                Some(&(_, players, wrap)) => {
+                    options.players = players;
+                    options.wrap = wrap;
+                    Ok(())
+                }
+                None => Err(format!("unknown mode {value}")),
+            },
+            ("display", "theme") => theme::find(value).map(|theme| options.theme = theme),
+            ("display", "monochrome") => {
+                parse(key, value).map(|monochrome: bool| options.color = !monochrome)
+            }
+            ("keys", action) => options.keys.bind(action, value),
+            ("", _) => Err(format!("{key} is outside of any section")),
+            _ => Err(format!("unknown setting {key} in [{section}]")),
+        };
+        result.map_err(|e| format!("line {number}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// `options` as a config file, which [`apply`] reads back to the same
+/// settings; unset ones are commented out.
+pub fn format(options: &Options) -> String {
+    let mut out = String::from("# command-line options override these settings\n\n[game]\n");
+    let optional = |out: &mut String, key: &str, value: Option<String>, default: &str| {
+        match value {
+            Some(value) => writeln!(out, "{key} = {value}"),
+            None => writeln!(out, "# {key} = {default}"),
+        }
+        .unwrap();
+    };
+    let (speed, curve) = speed::difficulty(&options.difficulty)
+        .unwrap_or((GameConfig::DEFAULT_SPEED, GameConfig::DEFAULT_CURVE));
+    optional(
+        &mut out,
+        "width",
+        options.width.map(|w| w.to_string()),
+        "(fill the terminal)",
+    );
+    optional(
+        &mut out,
+        "height",
+        options.height.map(|h| h.to_string()),
+        "(fill the terminal)",
+    );
+    optional(
+        &mut out,
+        "speed",
+        options.speed.map(|s| s.to_string()),
+        &speed.as_millis().to_string(),
+    );
+    writeln!(out, "difficulty = {}", options.difficulty).unwrap();
+    optional(
+        &mut out,
+        "curve",
+        options.curve.as_ref().map(|c| c.to_string()),
+        &curve.to_string(),
+    );
+    if let Some((mode, ..)) = MODES
+        .iter()
+        .find(|&&(_, players, wrap)| players == options.players && wrap == options.wrap)
+    {
+        writeln!(out, "mode = {mode}").unwrap();
+    }
+
+    writeln!(out, "\n[display]").unwrap();
+    writeln!(out, "theme = {}", options.theme.name).unwrap();
+    writeln!(out, "monochrome = {}", !options.color).unwrap();
+
+    writeln!(out, "\n[keys]").unwrap();
+    for (action, _) in ACTIONS {
+        writeln!(out, "{action} = {}", options.keys.names(action)).unwrap();
+    }
+    out
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+use std::io;
+
+/// Why a game could not be played to its end.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading keys from or drawing to the terminal failed.
+    Terminal(io::Error),
+    /// Anything else, already worded for the player.
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Terminal(e) => write!(f, "terminal: {e}"),
+            Error::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Terminal(e) => Some(e),
+            Error::Other(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Terminal(e)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Other(message)
+    }
+}
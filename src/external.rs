@@ -0,0 +1,224 @@
+use crate::bot::{Bot, BotError};
+use crate::game::{Block, Direction, Game, Position};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time an external bot gets to answer a single step.
+pub const DEFAULT_BUDGET: Duration = Duration::from_millis(100);
+
+/// Extra time for the first step, which includes starting the program.
+const STARTUP_TIME: Duration = Duration::from_secs(1);
+
+/// A bot running as a separate program, written in any language.
+///
+/// Before every step the program receives the board as one line of JSON on
+/// its standard input, see [`state`], and answers with one line naming the
+/// direction to move in: `up`, `"up"` or `{"direction": "up"}`. A reply that
+/// is late, malformed or missing because the program exited is recorded in
+/// [`Bot::errors`] and the snake keeps its direction. The program's standard
+/// error is passed through unless it is started `quiet`.
+pub struct ExternalBot {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    replies: Receiver<String>,
+    budget: Duration,
+    started: bool,
+    /// Replies still to come for steps that already timed out.
+    late: usize,
+    errors: Vec<BotError>,
+}
+
+impl ExternalBot {
+    /// Starts `command`, split on whitespace into the program and its
+    /// arguments, giving it `budget` to answer every step. A `quiet` bot's
+    /// standard error is discarded, so it cannot write over a board drawn in
+    /// the terminal.
+    pub fn spawn(command: &str, budget: Duration, quiet: bool) -> Result<ExternalBot, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("the bot command is empty")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(if quiet {
+                Stdio::null()
+            } else {
+                Stdio::inherit()
+            })
+            .spawn()
+            .map_err(|e| format!("cannot start bot {program}: {e}"))?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ExternalBot {
+            stdin: child.stdin.take(),
+            child,
+            replies,
+            budget,
+            started: false,
+            late: 0,
+            errors: Vec::new(),
+        })
+    }
+
+    fn error(&mut self, game: &Game, message: String) {
+        self.errors.push(BotError {
+            tick: game.tick(),
+            message,
+        });
+    }
+
+    /// Sends the state and waits for the reply, within the budget.
+    fn ask(&mut self, game: &Game, player: usize) -> Result<Direction, String> {
+        let stdin = self.stdin.as_mut().ok_or("the bot is no longer running")?;
+        let mut deadline = Instant::now() + self.budget;
+        if !self.started {
+            deadline += STARTUP_TIME;
+            self.started = true;
+        }
+        if let Err(e) = writeln!(stdin, "{}", state(game, player)).and_then(|_| stdin.flush()) {
+            self.stdin = None;
+            return Err(format!("cannot send the board to the bot: {e}"));
+        }
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.replies.recv_timeout(remaining) {
+                Ok(_) if self.late > 0 => self.late -= 1,
+                Ok(reply) => return parse_reply(&reply),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.late += 1;
+                    return Err(format!("no reply within {} ms", self.budget.as_millis()));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.stdin = None;
+                    return Err("the bot exited".to_string());
+                }
+            }
+        }
+    }
+}
+
+impl Bot for ExternalBot {
+    fn next_direction(&mut self, game: &Game, player: usize) -> Direction {
+        let direction = game.snakes()[player].direction();
+        // the error was recorded when the bot stopped running
+        if self.stdin.is_none() {
+            return direction;
+        }
+        match self.ask(game, player) {
+            Ok(direction) => direction,
+            Err(message) => {
+                self.error(game, message);
+                direction
+            }
+        }
+    }
+
+    fn errors(&self) -> &[BotError] {
+        &self.errors
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn position(position: Position) -> String {
+    format!("{{\"x\":{},\"y\":{}}}", position.x, position.y)
+}
+
+fn positions(positions: impl IntoIterator<Item = Position>) -> String {
+    let items: Vec<String> = positions.into_iter().map(position).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// The board as seen by `player`, as sent to an external bot.
+///
+/// ```json
+/// {"tick":12,"player":0,"width":30,"height":20,"wrap":false,
+///  "direction":"up","snake":[{"x":5,"y":3},{"x":5,"y":4}],
+///  "food":{"x":9,"y":9},"opponents":[[{"x":1,"y":1}]],"walls":[]}
+/// ```
+///
+/// Snakes are listed head first, `food` is `null` once the board is full and
+/// `opponents` holds the other living snakes.
+pub fn state(game: &Game, player: usize) -> String {
+    let field = game.field();
+    let snake = &game.snakes()[player];
+    let opponents: Vec<String> = game
+        .snakes()
+        .iter()
+        .enumerate()
+        .filter(|(other, snake)| *other != player && snake.alive())
+        .map(|(_, snake)| positions(snake.body().iter().copied()))
+        .collect();
+    let walls = field.rows().iter().enumerate().flat_map(|(y, row)| {
+        row.iter()
+            .enumerate()
+            .filter(|(_, block)| **block == Block::Wall)
+            .map(move |(x, _)| Position {
+                x: x as isize,
+                y: y as isize,
+            })
+    });
+
+    format!(
+        concat!(
+            "{{\"tick\":{},\"player\":{},\"width\":{},\"height\":{},\"wrap\":{},",
+            "\"direction\":\"{}\",\"snake\":{},\"food\":{},\"opponents\":[{}],\"walls\":{}}}"
+        ),
+        game.tick(),
+        player,
+        field.width(),
+        field.height(),
+        field.wraps(),
+        snake.direction(),
+        positions(snake.body().iter().copied()),
+        field.food().map_or("null".to_string(), position),
+        opponents.join(","),
+        positions(walls),
+    )
+}
+
+/// Reads a bot's reply: a bare direction, a JSON string or a JSON object
+/// with a `direction` member.
+fn parse_reply(reply: &str) -> Result<Direction, String> {
+    let reply = reply.trim();
+    let word = if reply.starts_with('{') {
+        json_member(reply, "direction")
+    } else {
+        Some(
+            reply
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(reply),
+        )
+    };
+    word.and_then(|word| word.parse().ok())
+        .ok_or_else(|| format!("invalid reply: {reply:?}"))
+}
+
+/// Value of the string member `key` of the flat JSON object `object`.
+fn json_member<'a>(object: &'a str, key: &str) -> Option<&'a str> {
+    let start = object.find(&format!("\"{key}\""))? + key.len() + 2;
+    let rest = object[start..].trim_start().strip_prefix(':')?;
+    let rest = rest.trim_start().strip_prefix('"')?;
+    rest.split('"').next()
+}
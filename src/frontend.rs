@@ -0,0 +1,340 @@
+use crate::error::Error;
+use crate::game::{Direction, Game, StepResult};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Player action read by an [`InputSource`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    /// Turn the given player's snake.
+    Turn(usize, Direction),
+    /// Take the given player's snake off the board, e.g. after they disconnected.
+    Leave(usize),
+    Pause,
+    /// The screen changed size, so the game has to be drawn again.
+    Resize,
+    Quit,
+}
+
+/// Entry of the pause menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Resume,
+    /// Start the game over with the same seed, so the food comes the same way.
+    Restart,
+    /// Start the game over with a new seed.
+    NewSeed,
+    /// Switch to the next theme.
+    Theme,
+    /// Leave the game for the menu around it, e.g. the campaign's.
+    QuitToMenu,
+    Quit,
+}
+
+impl MenuItem {
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuItem::Resume => "Resume",
+            MenuItem::Restart => "Restart",
+            MenuItem::NewSeed => "Restart with new seed",
+            MenuItem::Theme => "Theme",
+            MenuItem::QuitToMenu => "Quit to menu",
+            MenuItem::Quit => "Quit",
+        }
+    }
+}
+
+/// Pause menu of a game that can be started over.
+pub const PAUSE_MENU: &[MenuItem] = &[
+    MenuItem::Resume,
+    MenuItem::Restart,
+    MenuItem::NewSeed,
+    MenuItem::Theme,
+    MenuItem::Quit,
+];
+
+/// Pause menu of a campaign level, which can also be left for the screen
+/// between levels.
+pub const CAMPAIGN_MENU: &[MenuItem] = &[
+    MenuItem::Resume,
+    MenuItem::Restart,
+    MenuItem::NewSeed,
+    MenuItem::Theme,
+    MenuItem::QuitToMenu,
+    MenuItem::Quit,
+];
+
+/// Pause menu of a game that has to go on as it is, e.g. a replay or a
+/// network game.
+pub const PAUSE_MENU_NO_RESTART: &[MenuItem] = &[MenuItem::Resume, MenuItem::Theme, MenuItem::Quit];
+
+/// Key pressed in the pause menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Select,
+    /// Leave the menu and go on playing.
+    Close,
+    /// The screen changed size, so the game and menu have to be drawn again.
+    Resize,
+    Quit,
+}
+
+/// How a call to [`run`] ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    /// The player picked [`MenuItem::QuitToMenu`].
+    Menu,
+    Quit,
+}
+
+/// Draws the game state somewhere, e.g. to a terminal.
+pub trait Renderer {
+    fn draw(&mut self, game: &Game) -> Result<(), Error>;
+
+    /// Whether all of `game` can be drawn, which may change when the screen
+    /// is resized.
+    fn fits(&mut self, _game: &Game) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    /// Marks `player`'s score in games with several snakes, e.g. the snake a
+    /// spectator follows.
+    fn highlight(&mut self, player: Option<usize>);
+
+    /// Shows a message between games, e.g. before the next campaign level or
+    /// in a network lobby.
+    fn show_message(&mut self, message: &str) -> Result<(), Error>;
+
+    /// Draws `menu` over `game` as last drawn, marking the entry at
+    /// `selected`.
+    fn draw_menu(
+        &mut self,
+        _game: &Game,
+        _menu: &[MenuItem],
+        _selected: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Switches to the next theme, for renderers that have several.
+    fn next_theme(&mut self) {}
+}
+
+/// Supplies player input to the game loop.
+pub trait InputSource {
+    /// Waits at most `timeout` for the next input to `game`.
+    fn poll(&mut self, game: &Game, timeout: Duration) -> Result<Option<Input>, Error>;
+
+    /// Blocks until the player presses a key that does something in the
+    /// pause menu, or the screen is resized.
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error>;
+
+    /// Blocks until the player presses a key, returning `false` if they asked to quit.
+    fn wait_for_key(&mut self) -> Result<bool, Error>;
+
+    /// Waits at most `timeout` for a key press, returning `Some(false)` if
+    /// the player asked to quit.
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error>;
+}
+
+impl<I: InputSource + ?Sized> InputSource for Box<I> {
+    fn poll(&mut self, game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        (**self).poll(game, timeout)
+    }
+
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        (**self).wait_for_menu()
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        (**self).wait_for_key()
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        (**self).poll_key(timeout)
+    }
+}
+
+/// Turns a player can queue up ahead of their snake.
+const MAX_QUEUED_TURNS: usize = 3;
+
+/// Turns a player gave faster than their snake moves, taken one per step so
+/// that none are lost.
+#[derive(Clone, Default)]
+pub struct TurnQueue {
+    turns: VecDeque<Direction>,
+}
+
+impl TurnQueue {
+    /// Queues `direction` for a snake currently heading `current`, unless
+    /// the queue is full or the turn would go the way the snake is already
+    /// heading by then, or back into its neck.
+    pub fn push(&mut self, current: Direction, direction: Direction) {
+        let heading = self.turns.back().copied().unwrap_or(current);
+        if self.turns.len() < MAX_QUEUED_TURNS
+            && direction != heading
+            && direction != heading.opposite()
+        {
+            self.turns.push_back(direction);
+        }
+    }
+
+    /// The turn for the next step.
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.turns.pop_front()
+    }
+}
+
+/// Shows `menu` over `game` until the player leaves it, returning the entry
+/// that ended the pause: [`MenuItem::Resume`] if they closed the menu.
+///
+/// The game only goes on once all of it can be drawn.
+fn pause(
+    game: &Game,
+    renderer: &mut impl Renderer,
+    input: &mut impl InputSource,
+    menu: &[MenuItem],
+) -> Result<MenuItem, Error> {
+    let mut selected = 0;
+    loop {
+        let fits = renderer.fits(game)?;
+        if fits {
+            renderer.draw_menu(game, menu, selected)?;
+        }
+        match input.wait_for_menu()? {
+            MenuInput::Up => selected = (selected + menu.len() - 1) % menu.len(),
+            MenuInput::Down => selected = (selected + 1) % menu.len(),
+            MenuInput::Select if menu[selected] == MenuItem::Theme => {
+                renderer.next_theme();
+                renderer.draw(game)?;
+            }
+            MenuInput::Select if fits => return Ok(menu[selected]),
+            MenuInput::Close if fits => return Ok(MenuItem::Resume),
+            MenuInput::Select | MenuInput::Close => (),
+            MenuInput::Resize => renderer.draw(game)?,
+            MenuInput::Quit => return Ok(MenuItem::Quit),
+        }
+    }
+}
+
+/// Drives `game` until it is won, lost or the input source asks to quit.
+///
+/// Steps come at the game's cycle time measured from the previous step, not
+/// from the last input, so pressing keys does not speed the snake up. While
+/// paused, `menu` is shown; restarting from it replaces `game` with a new one.
+pub fn run(
+    game: &mut Game,
+    renderer: &mut impl Renderer,
+    input: &mut impl InputSource,
+    menu: &[MenuItem],
+) -> Result<Outcome, Error> {
+    let mut queues = vec![TurnQueue::default(); game.snakes().len()];
+    let mut next_step = Instant::now() + game.cycle_time();
+    loop {
+        renderer.draw(game)?;
+
+        // take input until the step is due, and then whatever else is
+        // already pending, so several players can turn in the same tick
+        loop {
+            let timeout = next_step.saturating_duration_since(Instant::now());
+            let Some(event) = input.poll(game, timeout)? else {
+                if timeout.is_zero() {
+                    break;
+                }
+                continue;
+            };
+            let paused = match event {
+                Input::Turn(player, direction) => {
+                    if let (Some(queue), Some(snake)) =
+                        (queues.get_mut(player), game.snakes().get(player))
+                    {
+                        queue.push(snake.direction(), direction);
+                    }
+                    false
+                }
+                Input::Leave(player) => {
+                    game.remove(player);
+                    false
+                }
+                Input::Pause => true,
+                Input::Resize => {
+                    renderer.draw(game)?;
+                    !renderer.fits(game)?
+                }
+                Input::Quit => return Ok(Outcome::Quit),
+            };
+            if !paused {
+                continue;
+            }
+
+            match pause(game, renderer, input, menu)? {
+                MenuItem::Quit => return Ok(Outcome::Quit),
+                MenuItem::QuitToMenu => return Ok(Outcome::Menu),
+                item @ (MenuItem::Restart | MenuItem::NewSeed) => {
+                    let mut config = game.config().clone();
+                    if item == MenuItem::NewSeed {
+                        config.seed = rand::random();
+                    }
+                    *game = Game::new(&config);
+                    queues = vec![TurnQueue::default(); game.snakes().len()];
+                }
+                MenuItem::Resume | MenuItem::Theme => (),
+            }
+            renderer.draw(game)?;
+            next_step = Instant::now() + game.cycle_time();
+        }
+
+        let turns: Vec<Option<Direction>> = queues.iter_mut().map(TurnQueue::pop).collect();
+        match game.step(&turns) {
+            StepResult::Won => return Ok(Outcome::Won),
+            StepResult::Lost => return Ok(Outcome::Lost),
+            StepResult::Moved | StepResult::Ate => (),
+        }
+        // after falling behind by more than a step, e.g. because drawing was
+        // slow, carry on from now instead of rushing through the missed steps
+        next_step = (next_step + game.cycle_time()).max(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(queue: &mut TurnQueue) -> Vec<Direction> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn turn_queue_drops_reversals_and_repeats() {
+        let mut queue = TurnQueue::default();
+        queue.push(Direction::Up, Direction::Down);
+        queue.push(Direction::Up, Direction::Up);
+        queue.push(Direction::Up, Direction::Left);
+        // checked against the queued turn, not the snake's direction
+        queue.push(Direction::Up, Direction::Right);
+        queue.push(Direction::Up, Direction::Left);
+        queue.push(Direction::Up, Direction::Down);
+        assert!(drain(&mut queue) == [Direction::Left, Direction::Down]);
+    }
+
+    #[test]
+    fn turn_queue_is_capped() {
+        let mut queue = TurnQueue::default();
+        for direction in [
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+        ] {
+            queue.push(Direction::Up, direction);
+        }
+        assert_eq!(queue.turns.len(), MAX_QUEUED_TURNS);
+        assert!(drain(&mut queue) == [Direction::Left, Direction::Down, Direction::Right]);
+        assert!(queue.pop().is_none());
+    }
+}
@@ -0,0 +1,657 @@
+use crate::level::Level;
+use crate::speed::SpeedCurve;
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Outcome of a single simulation step.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+    Moved,
+    Ate,
+    /// The snake ate and reached the level's target length.
+    Won,
+    Lost,
+}
+
+/// Settings a game is created with.
+#[derive(Clone)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Initial time between two steps.
+    pub speed: Duration,
+    /// How the step time changes from there.
+    pub curve: SpeedCurve,
+    /// Whether the snake leaves the board on one side and enters on the opposite one.
+    pub wrap: bool,
+    pub seed: u64,
+    /// Number of snakes on the board.
+    pub players: usize,
+    /// Walls and start position; the board size must match the level's.
+    pub level: Option<Level>,
+}
+
+impl GameConfig {
+    pub const DEFAULT_SPEED: Duration = Duration::from_millis(300);
+    pub const DEFAULT_CURVE: SpeedCurve = SpeedCurve::Exponential(0.9997);
+
+    pub fn new(width: usize, height: usize, seed: u64) -> GameConfig {
+        GameConfig {
+            width,
+            height,
+            speed: Self::DEFAULT_SPEED,
+            curve: Self::DEFAULT_CURVE,
+            wrap: false,
+            seed,
+            players: 1,
+            level: None,
+        }
+    }
+
+    /// Name of the rules this configuration plays by; only scores of the same
+    /// mode and board size are comparable.
+    pub fn mode(&self) -> String {
+        let rules = match (self.players, self.wrap) {
+            (1, false) => "classic".to_string(),
+            (1, true) => "wrap".to_string(),
+            (players, false) => format!("{players}-player"),
+            (players, true) => format!("{players}-player wrap"),
+        };
+        match &self.level {
+            Some(level) => format!("{rules} level {}", level.name),
+            None => rules.to_string(),
+        }
+    }
+
+    /// Snake length that wins the game, if there is one.
+    pub fn target(&self) -> Option<usize> {
+        self.level.as_ref().and_then(|level| level.target)
+    }
+}
+
+/// Snake game state, independent of any terminal or frontend.
+pub struct Game {
+    snakes: Vec<Snake>,
+    field: Field,
+    cycle_time: Duration,
+    /// Sum of the time every step took.
+    elapsed: Duration,
+    config: GameConfig,
+    rng: StdRng,
+    tick: u64,
+    inputs: Vec<(u64, usize, Direction)>,
+    removals: Vec<(u64, usize)>,
+}
+
+/// What a snake ran into.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Death {
+    Wall,
+    /// The snake ran into its own body.
+    SelfCollision,
+    /// The snake ran into another snake's body or head.
+    Opponent,
+    /// The player left the game.
+    Left,
+}
+
+/// One player's snake, head first.
+pub struct Snake {
+    body: VecDeque<Position>,
+    direction: Direction,
+    death: Option<Death>,
+}
+
+impl Snake {
+    fn new(head: Position, direction: Direction) -> Snake {
+        Snake {
+            body: VecDeque::from([head]),
+            direction,
+            death: None,
+        }
+    }
+
+    pub fn body(&self) -> &VecDeque<Position> {
+        &self.body
+    }
+
+    pub fn head(&self) -> Position {
+        self.body[0]
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    pub fn death(&self) -> Option<Death> {
+        self.death
+    }
+
+    /// Length of the snake, including its head.
+    pub fn length(&self) -> usize {
+        self.body.len()
+    }
+
+    /// Number of food items eaten so far.
+    pub fn score(&self) -> usize {
+        self.body.len() - 1
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Position {
+    pub fn step(&self, direction: &Direction) -> Position {
+        let mut new = *self;
+        match direction {
+            Direction::Up => new.y -= 1,
+            Direction::Right => new.x += 1,
+            Direction::Down => new.y += 1,
+            Direction::Left => new.x -= 1,
+        }
+        new
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    Empty,
+    /// Body of the given player's snake.
+    Snake(usize),
+    /// Head of the given player's snake.
+    SnakeHead(usize),
+    Wall,
+    Food,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "up",
+            Direction::Right => "right",
+            Direction::Down => "down",
+            Direction::Left => "left",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Direction::Up),
+            "right" => Ok(Direction::Right),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            _ => Err(format!("unknown direction: {s}")),
+        }
+    }
+}
+
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn set(&mut self, direction: &Direction) {
+        if *direction != self.opposite() {
+            *self = *direction;
+        }
+    }
+}
+
+pub struct Field {
+    width: usize,
+    height: usize,
+    wrap: bool,
+    food: Option<Position>,
+    field: Vec<Vec<Block>>,
+}
+
+impl Field {
+    fn new(width: usize, height: usize, wrap: bool) -> Field {
+        Field {
+            width,
+            height,
+            wrap,
+            food: None,
+            field: vec![vec![Block::Empty; width]; height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    /// Where the food is, or `None` once the board is full.
+    pub fn food(&self) -> Option<Position> {
+        self.food
+    }
+
+    pub fn rows(&self) -> &[Vec<Block>] {
+        &self.field
+    }
+
+    /// Position reached by moving one block from `position` in `direction`.
+    pub fn neighbor(&self, position: Position, direction: &Direction) -> Position {
+        let mut new = position.step(direction);
+        if self.wrap {
+            new.x = new.x.rem_euclid(self.width as isize);
+            new.y = new.y.rem_euclid(self.height as isize);
+        }
+        new
+    }
+
+    fn set_position(&mut self, position: Position, block: Block) {
+        let x = position.x as usize;
+        let y = position.y as usize;
+        self.field[y][x] = block;
+    }
+
+    pub fn get_position(&self, position: Position) -> Block {
+        if position.x < 0 || position.x >= self.width as isize {
+            return Block::Wall;
+        }
+
+        if position.y < 0 || position.y >= self.height as isize {
+            return Block::Wall;
+        }
+
+        let x = position.x as usize;
+        let y = position.y as usize;
+
+        self.field[y][x]
+    }
+
+    fn place_food(&mut self, rng: &mut StdRng) {
+        let mut allowed: Vec<Position> = vec![];
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                let position = Position { x, y };
+                if let Block::Empty = self.get_position(position) {
+                    allowed.push(position)
+                }
+            }
+        }
+
+        self.food = allowed.choose(rng).copied();
+        if let Some(food) = self.food {
+            self.set_position(food, Block::Food);
+        }
+    }
+}
+
+impl Game {
+    /// Creates a new game; the same seed always produces the same food placement.
+    pub fn new(config: &GameConfig) -> Game {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut field = Field::new(config.width, config.height, config.wrap);
+        if let Some(level) = &config.level {
+            for wall in &level.walls {
+                field.set_position(*wall, Block::Wall);
+            }
+        }
+
+        let snakes: Vec<Snake> = (0..config.players)
+            .map(|player| {
+                let (head, direction) = Self::start(config, player);
+                field.set_position(head, Block::SnakeHead(player));
+                Snake::new(head, direction)
+            })
+            .collect();
+        field.place_food(&mut rng);
+
+        Game {
+            snakes,
+            field,
+            cycle_time: config.speed,
+            elapsed: Duration::ZERO,
+            config: config.clone(),
+            rng,
+            tick: 0,
+            inputs: vec![],
+            removals: vec![],
+        }
+    }
+
+    /// Where and in which direction `player` starts. A single snake starts in
+    /// the middle, or at the level's start; more snakes start in rows on
+    /// alternating sides, facing the opposite side.
+    fn start(config: &GameConfig, player: usize) -> (Position, Direction) {
+        if let Some(level) = &config.level {
+            return (level.start, Direction::Right);
+        }
+
+        let width = config.width as isize;
+        let height = config.height as isize;
+        if config.players == 1 {
+            return (
+                Position {
+                    x: width / 2,
+                    y: height / 2,
+                },
+                Direction::Right,
+            );
+        }
+
+        let y = (player as isize + 1) * height / (config.players as isize + 1);
+        if player.is_multiple_of(2) {
+            (Position { x: width / 4, y }, Direction::Right)
+        } else {
+            (
+                Position {
+                    x: width - 1 - width / 4,
+                    y,
+                },
+                Direction::Left,
+            )
+        }
+    }
+
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    /// Length of the first player's snake, including its head.
+    pub fn length(&self) -> usize {
+        self.snakes[0].length()
+    }
+
+    /// Number of food items the first player has eaten so far.
+    pub fn score(&self) -> usize {
+        self.snakes[0].score()
+    }
+
+    /// Whether the first player's snake has reached the target length of the
+    /// level, or a single snake has filled the whole board.
+    pub fn won(&self) -> bool {
+        let target = self.config.target();
+        target.is_some_and(|target| self.length() >= target)
+            || (self.snakes.len() == 1 && self.field.food.is_none())
+    }
+
+    /// Whether the game has ended: every snake is dead, or with several
+    /// players, at most one is left.
+    pub fn over(&self) -> bool {
+        let alive = self.snakes.iter().filter(|snake| snake.alive()).count();
+        alive == 0 || (self.snakes.len() > 1 && alive == 1)
+    }
+
+    /// Number of steps taken so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Every input passed to [`Game::step`], together with the tick it was
+    /// applied on and the player it was for.
+    pub fn inputs(&self) -> &[(u64, usize, Direction)] {
+        &self.inputs
+    }
+
+    /// Every snake taken off the board by [`Game::remove`], together with the
+    /// tick it was removed on.
+    pub fn removals(&self) -> &[(u64, usize)] {
+        &self.removals
+    }
+
+    /// Time the frontend should wait for input before the next step.
+    pub fn cycle_time(&self) -> Duration {
+        self.cycle_time
+    }
+
+    /// Time the game has been played, counting every step as its cycle
+    /// time, so time spent paused does not count.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Takes `player`'s snake off the board before the next step, e.g.
+    /// because the player disconnected.
+    pub fn remove(&mut self, player: usize) {
+        let Some(snake) = self.snakes.get_mut(player).filter(|snake| snake.alive()) else {
+            return;
+        };
+        snake.death = Some(Death::Left);
+        for position in &snake.body {
+            self.field.set_position(*position, Block::Empty);
+        }
+        self.removals.push((self.tick, player));
+    }
+
+    /// Advances the game by one tick, first turning every snake whose player
+    /// gave a direction in `inputs`, which is indexed by player.
+    pub fn step(&mut self, inputs: &[Option<Direction>]) -> StepResult {
+        for (player, input) in inputs.iter().enumerate() {
+            if let (Some(direction), Some(snake)) = (input, self.snakes.get_mut(player)) {
+                self.inputs.push((self.tick, player, *direction));
+                snake.direction.set(direction);
+            }
+        }
+
+        let result = self.update();
+        self.tick += 1;
+        self.elapsed += self.cycle_time;
+        if result != StepResult::Lost {
+            let ate = matches!(result, StepResult::Ate | StepResult::Won);
+            let length = self.snakes.iter().map(Snake::length).max().unwrap_or(0);
+            self.cycle_time =
+                self.config
+                    .curve
+                    .next(self.config.speed, self.cycle_time, ate, length);
+        }
+        result
+    }
+
+    /// Moves all snakes at once. Every snake that runs into a wall or into
+    /// any snake's body as it was before the move dies, as do snakes whose
+    /// heads meet on the same block.
+    fn update(&mut self) -> StepResult {
+        let moves: Vec<Option<Position>> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                snake
+                    .alive()
+                    .then(|| self.field.neighbor(snake.head(), &snake.direction))
+            })
+            .collect();
+
+        let deaths: Vec<Option<Death>> = moves
+            .iter()
+            .enumerate()
+            .map(|(player, new_head)| {
+                let new_head = (*new_head)?;
+                match self.field.get_position(new_head) {
+                    Block::Wall => Some(Death::Wall),
+                    Block::Snake(other) | Block::SnakeHead(other) if other == player => {
+                        Some(Death::SelfCollision)
+                    }
+                    Block::Snake(_) | Block::SnakeHead(_) => Some(Death::Opponent),
+                    Block::Empty | Block::Food => moves
+                        .iter()
+                        .enumerate()
+                        .any(|(other, head)| other != player && *head == Some(new_head))
+                        .then_some(Death::Opponent),
+                }
+            })
+            .collect();
+
+        for (snake, death) in self.snakes.iter_mut().zip(deaths) {
+            if death.is_some() {
+                snake.death = death;
+                for position in &snake.body {
+                    self.field.set_position(*position, Block::Empty);
+                }
+            }
+        }
+
+        let mut ate = false;
+        for (player, new_head) in moves.into_iter().enumerate() {
+            let snake = &mut self.snakes[player];
+            let Some(new_head) = new_head.filter(|_| snake.alive()) else {
+                continue;
+            };
+
+            let old_head = snake.head();
+            let grows = self.field.get_position(new_head) == Block::Food;
+            snake.body.push_front(new_head);
+            self.field.set_position(new_head, Block::SnakeHead(player));
+            self.field.set_position(old_head, Block::Snake(player));
+            if grows {
+                ate = true;
+            } else {
+                let tail = snake.body.pop_back().unwrap();
+                self.field.set_position(tail, Block::Empty);
+            }
+        }
+        if ate {
+            self.field.place_food(&mut self.rng);
+        }
+
+        if self.over() {
+            StepResult::Lost
+        } else if self.won() {
+            StepResult::Won
+        } else if ate {
+            StepResult::Ate
+        } else {
+            StepResult::Moved
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game on a 12x6 board with the food in the top left corner and the
+    /// given snakes, as their blocks head first and their direction.
+    fn board(snakes: &[(&[(isize, isize)], Direction)]) -> Game {
+        let mut config = GameConfig::new(12, 6, 0);
+        config.players = snakes.len();
+        let mut game = Game::new(&config);
+        game.field.field = vec![vec![Block::Empty; 12]; 6];
+        game.field.food = Some(Position { x: 0, y: 0 });
+        game.field
+            .set_position(Position { x: 0, y: 0 }, Block::Food);
+
+        for (player, (blocks, direction)) in snakes.iter().enumerate() {
+            let body: VecDeque<Position> = blocks.iter().map(|&(x, y)| Position { x, y }).collect();
+            for (i, position) in body.iter().enumerate() {
+                let block = if i == 0 {
+                    Block::SnakeHead(player)
+                } else {
+                    Block::Snake(player)
+                };
+                game.field.set_position(*position, block);
+            }
+            game.snakes[player] = Snake {
+                body,
+                direction: *direction,
+                death: None,
+            };
+        }
+        game
+    }
+
+    fn deaths(game: &Game) -> Vec<Option<Death>> {
+        game.snakes().iter().map(Snake::death).collect()
+    }
+
+    #[test]
+    fn head_on_collision_kills_both_snakes() {
+        let mut game = board(&[
+            (&[(4, 2), (3, 2)], Direction::Right),
+            (&[(5, 2), (6, 2)], Direction::Left),
+        ]);
+        assert!(game.step(&[]) == StepResult::Lost);
+        assert!(deaths(&game) == [Some(Death::Opponent), Some(Death::Opponent)]);
+    }
+
+    #[test]
+    fn heads_meeting_on_one_block_kill_both_snakes() {
+        let mut game = board(&[
+            (&[(4, 2), (3, 2)], Direction::Right),
+            (&[(6, 2), (7, 2)], Direction::Left),
+            (&[(9, 5), (10, 5)], Direction::Left),
+        ]);
+        // one snake left ends the game
+        assert!(game.step(&[]) == StepResult::Lost);
+        assert!(deaths(&game) == [Some(Death::Opponent), Some(Death::Opponent), None]);
+        // the dead snakes are taken off the board
+        assert!(game.field().get_position(Position { x: 3, y: 2 }) == Block::Empty);
+    }
+
+    #[test]
+    fn running_into_a_body_kills_only_that_snake() {
+        let mut game = board(&[
+            (&[(4, 2), (3, 2)], Direction::Right),
+            (&[(5, 1), (5, 2), (5, 3)], Direction::Up),
+        ]);
+        assert!(game.step(&[]) == StepResult::Lost);
+        assert!(deaths(&game) == [Some(Death::Opponent), None]);
+        assert!(game.snakes()[1].head() == Position { x: 5, y: 0 });
+    }
+
+    #[test]
+    fn turns_apply_before_the_move() {
+        let mut game = board(&[
+            (&[(4, 2), (3, 2)], Direction::Right),
+            (&[(6, 2), (7, 2)], Direction::Left),
+        ]);
+        // turning away from the shared block avoids the collision
+        let result = game.step(&[Some(Direction::Up), Some(Direction::Down)]);
+        assert!(result == StepResult::Moved);
+        assert!(deaths(&game) == [None, None]);
+        assert!(game.snakes()[0].head() == Position { x: 4, y: 1 });
+        assert!(game.snakes()[1].head() == Position { x: 6, y: 3 });
+    }
+}
@@ -0,0 +1,150 @@
+use crate::frontend::Input;
+use crate::game::Direction;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Every input a key can be bound to, with its name in the config file.
+pub const ACTIONS: [(&str, Input); 10] = [
+    ("up", Input::Turn(0, Direction::Up)),
+    ("down", Input::Turn(0, Direction::Down)),
+    ("left", Input::Turn(0, Direction::Left)),
+    ("right", Input::Turn(0, Direction::Right)),
+    ("p2-up", Input::Turn(1, Direction::Up)),
+    ("p2-down", Input::Turn(1, Direction::Down)),
+    ("p2-left", Input::Turn(1, Direction::Left)),
+    ("p2-right", Input::Turn(1, Direction::Right)),
+    ("pause", Input::Pause),
+    ("quit", Input::Quit),
+];
+
+/// Names of keys that are not a single character.
+const NAMED_KEYS: [(&str, KeyCode); 9] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("esc", KeyCode::Esc),
+];
+
+/// Parses a key name like `w`, `up` or `ctrl-c`.
+pub fn parse_key(name: &str) -> Result<KeyEvent, String> {
+    let (modifiers, key) = match name.strip_prefix("ctrl-") {
+        Some(key) => (KeyModifiers::CONTROL, key),
+        None => (KeyModifiers::NONE, name),
+    };
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(named, _)| *named == key)
+            .map(|(_, code)| *code)
+            .ok_or_else(|| format!("unknown key {name}"))?,
+    };
+    let modifiers = match code {
+        KeyCode::Char(c) if c.is_uppercase() => modifiers | KeyModifiers::SHIFT,
+        _ => modifiers,
+    };
+    Ok(KeyEvent { code, modifiers })
+}
+
+/// Name of `key` as [`parse_key`] reads it.
+pub fn key_name(key: KeyEvent) -> String {
+    let name = match NAMED_KEYS.iter().find(|(_, code)| *code == key.code) {
+        Some((name, _)) => name.to_string(),
+        None => match key.code {
+            KeyCode::Char(c) => c.to_string(),
+            code => format!("{code:?}").to_lowercase(),
+        },
+    };
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        format!("ctrl-{name}")
+    } else {
+        name
+    }
+}
+
+/// Keys bound to each input.
+///
+/// Turn keys of the second player also steer the first player's snake when
+/// there is only one.
+#[derive(Clone)]
+pub struct Keys {
+    bindings: Vec<(Input, Vec<KeyEvent>)>,
+}
+
+impl Default for Keys {
+    /// Player 1 on WASD and player 2 on the arrows or hjkl, `p` to pause and
+    /// Ctrl+C to quit.
+    fn default() -> Self {
+        let mut keys = Keys {
+            bindings: ACTIONS.iter().map(|(_, input)| (*input, vec![])).collect(),
+        };
+        for (action, names) in [
+            ("up", "w"),
+            ("down", "s"),
+            ("left", "a"),
+            ("right", "d"),
+            ("p2-up", "up k"),
+            ("p2-down", "down j"),
+            ("p2-left", "left h"),
+            ("p2-right", "right l"),
+            ("pause", "p"),
+            ("quit", "ctrl-c"),
+        ] {
+            keys.bind(action, names).expect("default keys are valid");
+        }
+        keys
+    }
+}
+
+impl Keys {
+    /// Binds the space-separated key `names` to `action`, one of the names
+    /// in [`ACTIONS`], replacing its keys and unbinding them from any other
+    /// action.
+    pub fn bind(&mut self, action: &str, names: &str) -> Result<(), String> {
+        let (_, input) = ACTIONS
+            .iter()
+            .find(|(name, _)| *name == action)
+            .ok_or_else(|| format!("unknown action {action}"))?;
+        let keys = names
+            .split_whitespace()
+            .map(parse_key)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (bound, bound_keys) in &mut self.bindings {
+            if bound == input {
+                *bound_keys = keys.clone();
+            } else {
+                bound_keys.retain(|key| !keys.contains(key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Names of the keys bound to `action`, separated by spaces.
+    pub fn names(&self, action: &str) -> String {
+        let input = ACTIONS
+            .iter()
+            .find(|(name, _)| *name == action)
+            .map(|a| a.1);
+        self.bindings
+            .iter()
+            .filter(|(bound, _)| Some(*bound) == input)
+            .flat_map(|(_, keys)| keys.iter().map(|key| key_name(*key)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// What `key` does in a game of `players` players.
+    pub fn input(&self, key: KeyEvent, players: usize) -> Option<Input> {
+        let (input, _) = self.bindings.iter().find(|(_, keys)| keys.contains(&key))?;
+        match *input {
+            Input::Turn(player, direction) if player >= players => Some(Input::Turn(0, direction)),
+            input => Some(input),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::game::Position;
+use std::fmt;
+use std::fs;
+use std::time::Duration;
+
+/// Levels shipped with the game, in campaign order, as `(name, source)`.
+pub const BUNDLED: &[(&str, &str)] = &[
+    ("pillars", include_str!("../levels/pillars.txt")),
+    ("cross", include_str!("../levels/cross.txt")),
+    ("rooms", include_str!("../levels/rooms.txt")),
+    ("corridors", include_str!("../levels/corridors.txt")),
+];
+
+/// A board layout with internal walls, loaded from a plain-text level file.
+///
+/// The file starts with an optional header of `key: value` lines (`name`,
+/// `speed` in milliseconds, `target` snake length) ended by a `---` line,
+/// followed by the grid: `#` is a wall, `.` is floor and `S` is where the
+/// snake starts.
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub speed: Option<Duration>,
+    /// Snake length that completes the level.
+    pub target: Option<usize>,
+    pub width: usize,
+    pub height: usize,
+    pub walls: Vec<Position>,
+    pub start: Position,
+    /// Text the level was parsed from.
+    pub source: String,
+}
+
+/// Why a level file could not be loaded, with a 1-based location in the file.
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+fn error(line: usize, column: usize, message: String) -> ParseError {
+    ParseError {
+        line,
+        column,
+        message,
+    }
+}
+
+impl Level {
+    /// Loads a bundled level by name, or else the level file at `path`.
+    pub fn load(path: &str) -> Result<Level, String> {
+        if let Some((_, source)) = BUNDLED.iter().find(|(name, _)| *name == path) {
+            return Level::parse(source).map_err(|e| format!("bundled level {path}: {e}"));
+        }
+
+        let source = fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+        Level::parse(&source).map_err(|e| format!("{path}: {e}"))
+    }
+
+    pub fn parse(source: &str) -> Result<Level, ParseError> {
+        let lines: Vec<&str> = source.lines().collect();
+        let grid_start = match lines.iter().position(|line| line.trim_end() == "---") {
+            Some(separator) => separator + 1,
+            None => 0,
+        };
+
+        let mut level = Level {
+            name: "custom".to_string(),
+            speed: None,
+            target: None,
+            width: 0,
+            height: 0,
+            walls: vec![],
+            start: Position { x: 0, y: 0 },
+            source: source.to_string(),
+        };
+
+        for (index, line) in lines[..grid_start.saturating_sub(1)].iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let number = index + 1;
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(error(number, 1, "expected `key: value`".to_string()));
+            };
+            let column = key.len() + 2 + (value.len() - value.trim_start().len());
+            let value = value.trim();
+            let invalid = || error(number, column, format!("invalid {}: {value}", key.trim()));
+
+            match key.trim() {
+                "name" => level.name = value.to_string(),
+                "speed" => match value.parse() {
+                    Ok(ms) if ms > 0 => level.speed = Some(Duration::from_millis(ms)),
+                    _ => return Err(invalid()),
+                },
+                "target" => match value.parse() {
+                    Ok(length) if length > 1 => level.target = Some(length),
+                    _ => return Err(invalid()),
+                },
+                key => return Err(error(number, 1, format!("unknown key: {key}"))),
+            }
+        }
+
+        let mut rows = &lines[grid_start..];
+        while let [rest @ .., last] = rows {
+            if !last.trim().is_empty() {
+                break;
+            }
+            rows = rest;
+        }
+        if rows.is_empty() {
+            return Err(error(grid_start + 1, 1, "level has no grid".to_string()));
+        }
+
+        let mut start = None;
+        for (y, row) in rows.iter().enumerate() {
+            let number = grid_start + y + 1;
+            let width = row.chars().count();
+            if width == 0 {
+                return Err(error(number, 1, "empty row".to_string()));
+            }
+            if y == 0 {
+                level.width = width;
+            } else if width != level.width {
+                return Err(error(
+                    number,
+                    width.min(level.width) + 1,
+                    format!("row is {width} blocks wide, expected {}", level.width),
+                ));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                let position = Position {
+                    x: x as isize,
+                    y: y as isize,
+                };
+                match c {
+                    '.' => (),
+                    '#' => level.walls.push(position),
+                    'S' if start.is_none() => start = Some(position),
+                    'S' => return Err(error(number, x + 1, "second start position".to_string())),
+                    c => return Err(error(number, x + 1, format!("unexpected character {c:?}"))),
+                }
+            }
+        }
+        level.height = rows.len();
+
+        level.start = start.ok_or_else(|| {
+            error(
+                grid_start + rows.len(),
+                1,
+                "level has no start position `S`".to_string(),
+            )
+        })?;
+        Ok(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line and column of the error parsing `source`.
+    fn error_at(source: &str) -> Option<(usize, usize)> {
+        Level::parse(source).err().map(|e| (e.line, e.column))
+    }
+
+    #[test]
+    fn parses_header_and_grid() {
+        let level = Level::parse("name: Box\ntarget: 5\n---\n#####\n#.S.#\n#####\n")
+            .ok()
+            .unwrap();
+        assert_eq!(level.name, "Box");
+        assert_eq!(level.target, Some(5));
+        assert_eq!((level.width, level.height), (5, 3));
+        assert_eq!(level.walls.len(), 12);
+        assert!(level.start == Position { x: 2, y: 1 });
+    }
+
+    #[test]
+    fn reports_where_errors_are() {
+        assert_eq!(error_at("colour: red\n---\n.S."), Some((1, 1)));
+        assert_eq!(error_at("name: x\nspeed:  fast\n---\n.S."), Some((2, 9)));
+        assert_eq!(error_at("---\n.S..\n.x.."), Some((3, 2)));
+        assert_eq!(error_at("---\n.S..\n.."), Some((3, 3)));
+        assert_eq!(error_at("---\n.S..\n...S"), Some((3, 4)));
+        assert_eq!(error_at("---\n....\n...."), Some((3, 1)));
+        assert_eq!(error_at("name: x\n---\n"), Some((3, 1)));
+    }
+}
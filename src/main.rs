@@ -1,6 +1,430 @@
+use snake::autopilot::Autopilot;
+use snake::bot::{self, Bot, BotError, BotInput};
+use snake::campaign::{Campaign, Progress};
+use snake::cli::{self, Command, Options};
+use snake::config;
+use snake::external::ExternalBot;
+use snake::net::{Connection, Server};
+use snake::replay::{Recording, ReplayInput};
+use snake::scores::{Entry, Scores};
+use snake::terminal::{field_size, KeyboardInput, TerminalRenderer};
 use snake::*;
+use std::env;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
 
-fn main() {
-    let mut game = Game::new();
-    game.play();
+/// Board size used when playing without a terminal.
+const HEADLESS_SIZE: (usize, usize) = (30, 20);
+
+/// Bot errors listed after a game; the rest are only counted.
+const SHOWN_BOT_ERRORS: usize = 10;
+
+/// Exit status after the player quit before the game was over.
+const QUIT: u8 = 1;
+
+/// Exit status after an error.
+const FAILURE: u8 = 2;
+
+fn print_result(game: &Game) {
+    if game.snakes().len() > 1 {
+        let alive: Vec<usize> = (0..game.snakes().len())
+            .filter(|&player| game.snakes()[player].alive())
+            .collect();
+        match alive[..] {
+            [winner] => println!("Player {} wins!", winner + 1),
+            _ => println!("Draw!"),
+        }
+    } else {
+        let heading = match (game.won(), game.config().target()) {
+            (true, Some(_)) => "Level complete!",
+            (true, None) => "You filled the board!",
+            (false, _) => "Game over!",
+        };
+        println!("{heading}");
+    }
+    print_scores(game);
+}
+
+fn print_scores(game: &Game) {
+    if game.snakes().len() > 1 {
+        for (player, snake) in game.snakes().iter().enumerate() {
+            println!("Player {} score: {}", player + 1, snake.score());
+        }
+    } else {
+        println!("Score: {}", game.score());
+    }
+    println!("Seed: {}", game.seed());
+}
+
+/// The bot asked for on the command line: the external `command`, the
+/// autopilot or none. Without a `terminal` drawing the board, the external
+/// bot's standard error is passed through.
+fn make_bot(
+    command: Option<&str>,
+    autopilot: bool,
+    budget: Duration,
+    terminal: bool,
+) -> Result<Option<Box<dyn Bot>>, Error> {
+    if let Some(command) = command {
+        Ok(Some(Box::new(ExternalBot::spawn(
+            command, budget, terminal,
+        )?)))
+    } else if autopilot {
+        Ok(Some(Box::<Autopilot>::default()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn print_bot_errors(errors: &[BotError]) {
+    if errors.is_empty() {
+        return;
+    }
+    eprintln!("\nBot errors: {}", errors.len());
+    for error in errors.iter().take(SHOWN_BOT_ERRORS) {
+        eprintln!("  {error}");
+    }
+    if errors.len() > SHOWN_BOT_ERRORS {
+        eprintln!("  and {} more", errors.len() - SHOWN_BOT_ERRORS);
+    }
+}
+
+fn save_score(name: &str, game: &Game) -> Result<(), String> {
+    let mut scores = Scores::load()?;
+    let entry = Entry::new(name, game, game.elapsed());
+    if let Some(rank) = scores.add(entry.clone()) {
+        println!("\nNew high score, you placed #{}!", rank + 1);
+        scores.save()?;
+    }
+    println!("\n{}", scores.format_table(&entry));
+    Ok(())
+}
+
+fn play(options: Options) -> Result<ExitCode, Error> {
+    if let Some(port) = options.host {
+        return host(options, port);
+    }
+    if options.headless {
+        return headless(options);
+    }
+    let record = options.record.clone();
+    let bot_command = options.bot.clone();
+    let autopilot = options.autopilot;
+    let bot_time = Duration::from_millis(options.bot_time);
+    let name = options
+        .name
+        .clone()
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_else(|| "player".to_string());
+    let (theme, color, keys) = (options.theme, options.color, options.keys.clone());
+    let (max_width, max_height) = field_size()?;
+    let config = options.into_config(max_width, max_height)?;
+    let bot = make_bot(bot_command.as_deref(), autopilot, bot_time, true)?;
+    let has_bot = bot.is_some();
+
+    let mut renderer = TerminalRenderer::new(theme, color)?;
+    if config.players == 1 && !has_bot {
+        // a broken score file is reported when the score is saved
+        renderer.set_best(Scores::load().ok().and_then(|scores| scores.best(&config)));
+    }
+    let mut game = Game::new(&config);
+    let mut keyboard = KeyboardInput::new(config.players, keys);
+    let (outcome, bot_errors) = if let Some(bot) = bot {
+        let player = config.players - 1;
+        let mut input = BotInput::new(bot, player, keyboard);
+        let outcome = run(&mut game, &mut renderer, &mut input, PAUSE_MENU)?;
+        (outcome, input.bot().errors().to_vec())
+    } else {
+        let outcome = run(&mut game, &mut renderer, &mut keyboard, PAUSE_MENU)?;
+        (outcome, Vec::new())
+    };
+    renderer.restore()?;
+    if outcome != Outcome::Quit {
+        print_result(&game);
+    }
+    print_bot_errors(&bot_errors);
+
+    if let Some(path) = record {
+        if let Err(message) = Recording::from_game(&game).save(&path) {
+            eprintln!("error: {message}");
+        }
+    }
+    match outcome {
+        Outcome::Won | Outcome::Lost if config.players == 1 && !has_bot => {
+            if let Err(message) = save_score(&name, &game) {
+                eprintln!("error: {message}");
+            }
+        }
+        Outcome::Won | Outcome::Lost => (),
+        Outcome::Menu | Outcome::Quit => return Ok(ExitCode::from(QUIT)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn headless(mut options: Options) -> Result<ExitCode, Error> {
+    options.width.get_or_insert(HEADLESS_SIZE.0);
+    options.height.get_or_insert(HEADLESS_SIZE.1);
+    let record = options.record.clone();
+    let bot_command = options.bot.clone();
+    let autopilot = options.autopilot;
+    let bot_time = Duration::from_millis(options.bot_time);
+    let config = options.into_config(usize::MAX, usize::MAX)?;
+    let bot = make_bot(bot_command.as_deref(), autopilot, bot_time, false)?
+        .expect("--headless needs a bot");
+
+    // like in the terminal, the chosen bot steers the last snake
+    let mut game = Game::new(&config);
+    let mut bots: Vec<Box<dyn Bot>> = (1..config.players)
+        .map(|_| Box::<Autopilot>::default() as Box<dyn Bot>)
+        .collect();
+    bots.push(bot);
+    let patience = bot::patience(&config);
+    let outcome = bot::play(&mut game, &mut bots, patience);
+
+    if outcome == Outcome::Quit {
+        println!("Stopped, no food eaten for {patience} steps.");
+        print_scores(&game);
+    } else {
+        print_result(&game);
+    }
+    println!("Steps: {}", game.tick());
+    print_bot_errors(bots[config.players - 1].errors());
+
+    if let Some(path) = record {
+        if let Err(message) = Recording::from_game(&game).save(&path) {
+            eprintln!("error: {message}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Draws nothing, for games nobody watches locally.
+struct NoRenderer;
+
+impl Renderer for NoRenderer {
+    fn draw(&mut self, _game: &Game) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn highlight(&mut self, _player: Option<usize>) {}
+
+    fn show_message(&mut self, _message: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Gives no input, only lets the time for each step pass.
+struct Clock;
+
+impl InputSource for Clock {
+    fn poll(&mut self, _game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        thread::sleep(timeout);
+        Ok(None)
+    }
+
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        Ok(MenuInput::Close)
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        thread::sleep(timeout);
+        Ok(None)
+    }
+}
+
+fn host(mut options: Options, port: u16) -> Result<ExitCode, Error> {
+    let headless = options.headless;
+    let (theme, color, keys) = (options.theme, options.color, options.keys.clone());
+    let record = options.record.clone();
+    let bot_command = options.bot.clone();
+    let autopilot = options.autopilot;
+    let bot_time = Duration::from_millis(options.bot_time);
+    let (max_width, max_height) = if headless {
+        options.width.get_or_insert(HEADLESS_SIZE.0);
+        options.height.get_or_insert(HEADLESS_SIZE.1);
+        (usize::MAX, usize::MAX)
+    } else {
+        field_size()?
+    };
+    let mut config = options.into_config(max_width, max_height)?;
+    let mut server = Server::host(port)?;
+    let bot = make_bot(bot_command.as_deref(), autopilot, bot_time, !headless)?;
+
+    let mut game;
+    let outcome = if headless {
+        // a match between bots, the chosen one steering the last snake
+        let mut input: Box<dyn InputSource> = Box::new(Clock);
+        for player in 0..config.players - 1 {
+            input = Box::new(BotInput::new(Autopilot::default(), player, input));
+        }
+        let bot = bot.expect("--headless needs a bot");
+        let mut input = BotInput::new(bot, config.players - 1, input);
+        println!(
+            "Hosting a bot match on port {}, watch it with: snake --spectate <address>:{}",
+            server.port(),
+            server.port()
+        );
+        server.start(&config);
+        game = Game::new(&config);
+        server.play(&mut game, &mut NoRenderer, &mut input)?
+    } else {
+        let mut renderer = TerminalRenderer::new(theme, color)?;
+        let mut input: Box<dyn InputSource> = Box::new(KeyboardInput::new(1, keys));
+        if let Some(bot) = bot {
+            input = Box::new(BotInput::new(bot, 0, input));
+        }
+        if !server.lobby(&mut renderer, &mut input)? {
+            return Ok(ExitCode::from(QUIT));
+        }
+        config.players = server.players();
+        server.start(&config);
+        game = Game::new(&config);
+        let outcome = server.play(&mut game, &mut renderer, &mut input)?;
+        renderer.restore()?;
+        outcome
+    };
+    print_result(&game);
+
+    if let Some(path) = record {
+        if let Err(message) = Recording::from_game(&game).save(&path) {
+            eprintln!("error: {message}");
+        }
+    }
+    if outcome == Outcome::Quit {
+        return Ok(ExitCode::from(QUIT));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Takes part in a network game as a player or, with `spectator`, watches it.
+fn connect(address: &str, options: Options, spectator: bool) -> Result<ExitCode, Error> {
+    let mut connection = if spectator {
+        Connection::spectate(address)?
+    } else {
+        Connection::join(address)?
+    };
+    let (max_width, max_height) = field_size()?;
+    let mut renderer = TerminalRenderer::new(options.theme, options.color)?;
+    let mut keyboard = KeyboardInput::new(1, options.keys);
+
+    let game = match connection.lobby(&mut renderer, &mut keyboard)? {
+        Some(recording)
+            if recording.config.width > max_width || recording.config.height > max_height =>
+        {
+            return Err(Error::Other(format!(
+                "the host's {}x{} board does not fit the terminal (at most {max_width}x{max_height})",
+                recording.config.width, recording.config.height
+            )));
+        }
+        Some(recording) => connection.play(&recording, &mut renderer, &mut keyboard)?,
+        None => None,
+    };
+    renderer.restore()?;
+    let Some(game) = game else {
+        return Ok(ExitCode::from(QUIT));
+    };
+    print_result(&game);
+    if let Some(player) = connection.player() {
+        println!("You were player {}.", player + 1);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn replay(path: &str, options: Options) -> Result<ExitCode, Error> {
+    let recording = Recording::load(path)?;
+    let (max_width, max_height) = field_size()?;
+    let config = &recording.config;
+    if config.width > max_width || config.height > max_height {
+        return Err(Error::Other(format!(
+            "the recorded {}x{} board does not fit the terminal (at most {max_width}x{max_height})",
+            config.width, config.height
+        )));
+    }
+
+    let mut renderer = TerminalRenderer::new(options.theme, options.color)?;
+    let mut game = Game::new(config);
+    let mut input = ReplayInput::new(&recording, KeyboardInput::new(config.players, options.keys));
+    run(&mut game, &mut renderer, &mut input, PAUSE_MENU_NO_RESTART)?;
+    renderer.restore()?;
+    print_result(&game);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn campaign(options: Options) -> Result<ExitCode, Error> {
+    let mut progress = Progress::load()?;
+    let (theme, color, keys) = (options.theme, options.color, options.keys.clone());
+    let speed = options.speed.map(Duration::from_millis);
+    let (max_width, max_height) = field_size()?;
+    // the levels bring their own boards, the rules are the same for all
+    let rules = options.into_config(max_width, max_height)?;
+    let mut campaign = Campaign::bundled(progress.unlocked, rules, speed)?;
+
+    for level in campaign.levels() {
+        if level.width > max_width || level.height > max_height {
+            return Err(Error::Other(format!(
+                "level {} ({}x{}) does not fit the terminal (at most {max_width}x{max_height})",
+                level.name, level.width, level.height
+            )));
+        }
+    }
+
+    let mut renderer = TerminalRenderer::new(theme, color)?;
+    let outcome = campaign.play(
+        &mut renderer,
+        &mut KeyboardInput::new(1, keys),
+        &mut progress,
+    )?;
+    renderer.restore()?;
+    match outcome {
+        Outcome::Won => println!("Campaign complete!"),
+        Outcome::Lost => println!("Out of lives on level {}.", campaign.level()),
+        Outcome::Menu | Outcome::Quit => {
+            println!("Campaign paused at level {}.", campaign.level())
+        }
+    }
+    println!("Score: {}", campaign.score());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn scores() -> Result<ExitCode, Error> {
+    println!("{}", Scores::load()?.format_all());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let mut options = Options::default();
+    if let Err(message) = config::load(&mut options) {
+        eprintln!("error: {message}");
+        return ExitCode::from(FAILURE);
+    }
+    let result = match cli::parse(env::args().skip(1), options) {
+        Ok(Command::Play(options)) => play(options),
+        Ok(Command::Replay(path, options)) => replay(&path, options),
+        Ok(Command::Join(address, options)) => connect(&address, options, false),
+        Ok(Command::Spectate(address, options)) => connect(&address, options, true),
+        Ok(Command::Campaign(options)) => campaign(options),
+        Ok(Command::PrintConfig(options)) => {
+            print!("{}", config::format(&options));
+            Ok(ExitCode::SUCCESS)
+        }
+        Ok(Command::Scores) => scores(),
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{}", cli::USAGE);
+            return ExitCode::from(FAILURE);
+        }
+    };
+    // every terminal guard is dropped by now, so the message is readable
+    result.unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        ExitCode::from(FAILURE)
+    })
 }
@@ -0,0 +1,616 @@
+use crate::error::Error;
+use crate::frontend::{
+    run, Input, InputSource, MenuInput, Outcome, Renderer, PAUSE_MENU_NO_RESTART,
+};
+use crate::game::{Direction, Game, GameConfig};
+use crate::replay::{Recording, LEAVE};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// First line both ends of a connection send.
+///
+/// The protocol is line based. After this line a client sends `join` to play
+/// or `spectate` to watch. Players then send `ready` once they are ready and
+/// `turn <direction>` to steer their snake. The server sends
+/// `lobby <players> <ready>` while waiting for players, then `player <index>`
+/// to players and `start <count>` followed by `count` lines of a
+/// [`Recording`] of the game so far. After every step it sends what changed,
+/// as recording lines `<tick> <player> <direction>` and
+/// `<tick> <player> leave`, followed by `step <tick>`, and `end` once the
+/// game is over. Either side may send `error <message>` before closing the
+/// connection.
+pub const PROTOCOL: &str = "snake-net 2";
+
+/// Most snakes a network game can have, the host's included.
+pub const MAX_PLAYERS: usize = 4;
+
+/// How long to wait for a key before checking the network again.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Time the other end gets to introduce itself or to take a line.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the connection threads report to the server.
+enum Event {
+    /// A client connected, to play or, if the flag is set, to watch.
+    Joined(usize, TcpStream, bool),
+    Ready(usize),
+    Turn(usize, Direction),
+    Left(usize),
+}
+
+struct Client {
+    id: usize,
+    stream: TcpStream,
+    ready: bool,
+}
+
+impl Client {
+    fn new(id: usize, stream: TcpStream) -> Client {
+        let _ = stream.set_nodelay(true);
+        let _ = stream.set_write_timeout(Some(TIMEOUT));
+        Client {
+            id,
+            stream,
+            ready: false,
+        }
+    }
+
+    fn send(&mut self, text: &str) {
+        if self.stream.write_all(text.as_bytes()).is_err() {
+            // its thread notices and reports the client as gone
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn reject(mut stream: TcpStream, message: &str) {
+    let _ = writeln!(stream, "error {message}");
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// The start of a game for clients, holding every step sent so far.
+fn start_message(recording: &Recording) -> String {
+    let text = recording.to_text();
+    format!("start {}\n{text}", text.lines().count())
+}
+
+/// Reads one client's lines until it disconnects.
+fn serve(id: usize, mut stream: TcpStream, events: Sender<Event>) {
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let mut lines = BufReader::new(reader).lines();
+    if !matches!(lines.next(), Some(Ok(line)) if line == PROTOCOL) {
+        return reject(stream, &format!("expected {PROTOCOL}"));
+    }
+    let spectator = match lines.next() {
+        Some(Ok(line)) if line == "join" => false,
+        Some(Ok(line)) if line == "spectate" => true,
+        _ => return reject(stream, "expected join or spectate"),
+    };
+    let _ = stream.set_read_timeout(None);
+    if writeln!(stream, "{PROTOCOL}").is_err()
+        || events.send(Event::Joined(id, stream, spectator)).is_err()
+    {
+        return;
+    }
+
+    for line in lines.map_while(Result::ok) {
+        let event = match line.split_once(' ') {
+            None if line == "ready" => Event::Ready(id),
+            Some(("turn", direction)) => match direction.parse() {
+                Ok(direction) => Event::Turn(id, direction),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+    let _ = events.send(Event::Left(id));
+}
+
+/// Hosts a network game: runs the simulation and keeps every client's copy
+/// of it in step.
+///
+/// The host plays player 1; clients get the following players in the order
+/// they joined. A client that disconnects has its snake removed. Spectators
+/// may join at any time and are sent the game so far.
+pub struct Server {
+    port: u16,
+    events: Receiver<Event>,
+    clients: Vec<Client>,
+    spectators: Vec<Client>,
+    started: Arc<AtomicBool>,
+    /// Number of ticks whose changes have been sent to the clients.
+    sent: u64,
+}
+
+impl Server {
+    /// Starts listening for clients on `port`, on every interface.
+    pub fn host(port: u16) -> Result<Server, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| format!("cannot listen on port {port}: {e}"))?;
+        let port = listener.local_addr().map_or(port, |address| address.port());
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().map_while(Result::ok).enumerate() {
+                let sender = sender.clone();
+                thread::spawn(move || serve(id, stream, sender));
+            }
+        });
+
+        Ok(Server {
+            port,
+            events,
+            clients: Vec::new(),
+            spectators: Vec::new(),
+            started: Arc::new(AtomicBool::new(false)),
+            sent: 0,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Number of players, the host included.
+    pub fn players(&self) -> usize {
+        self.clients.len() + 1
+    }
+
+    fn player(&self, id: usize) -> Option<usize> {
+        let index = self.clients.iter().position(|client| client.id == id)?;
+        Some(index + 1)
+    }
+
+    /// Applies `event` to the list of clients, returning what it means for
+    /// the running `game`, if there is one yet.
+    fn handle(&mut self, event: Event, game: Option<&Game>) -> Option<Input> {
+        let started = self.started.load(Ordering::Relaxed);
+        match event {
+            Event::Joined(id, stream, true) => {
+                let mut spectator = Client::new(id, stream);
+                if let Some(game) = game {
+                    spectator.send(&start_message(&self.recording(game)));
+                }
+                self.spectators.push(spectator);
+            }
+            Event::Joined(_, stream, false) if started => {
+                reject(stream, "the game has already started")
+            }
+            Event::Joined(_, stream, false) if self.players() == MAX_PLAYERS => {
+                reject(stream, "the game is full")
+            }
+            Event::Joined(id, stream, false) => self.clients.push(Client::new(id, stream)),
+            Event::Ready(id) => {
+                if let Some(client) = self.clients.iter_mut().find(|client| client.id == id) {
+                    client.ready = true;
+                }
+            }
+            Event::Turn(id, direction) if started => {
+                return Some(Input::Turn(self.player(id)?, direction));
+            }
+            Event::Turn(..) => (),
+            Event::Left(id) => {
+                self.spectators.retain(|spectator| spectator.id != id);
+                // players keep their numbers once the game runs
+                if started {
+                    return Some(Input::Leave(self.player(id)?));
+                }
+                self.clients.retain(|client| client.id != id);
+            }
+        }
+        None
+    }
+
+    fn broadcast(&mut self, text: &str) {
+        for client in self.clients.iter_mut().chain(&mut self.spectators) {
+            client.send(text);
+        }
+    }
+
+    fn lobby_message(&self, ready: bool) -> String {
+        let mut message = format!(
+            "Hosting a game on port {}.\nOthers join with: snake --join <address>:{}\n\nPlayers:\n",
+            self.port, self.port
+        );
+        let status = |ready| if ready { "ready" } else { "not ready" };
+        message += &format!("  Player 1 (you): {}\n", status(ready));
+        for (index, client) in self.clients.iter().enumerate() {
+            message += &format!("  Player {}: {}\n", index + 2, status(client.ready));
+        }
+        if !self.spectators.is_empty() {
+            message += &format!("Spectators: {}\n", self.spectators.len());
+        }
+
+        message += if !ready {
+            "\nPress any key when you are ready, Ctrl+C to quit."
+        } else if self.clients.is_empty() {
+            "\nWaiting for players to join."
+        } else {
+            "\nThe game starts once every player is ready."
+        };
+        message
+    }
+
+    /// Shows the lobby until the host and at least one client are ready and
+    /// no client is not, returning `false` if the host quit.
+    pub fn lobby(
+        &mut self,
+        renderer: &mut impl Renderer,
+        input: &mut impl InputSource,
+    ) -> Result<bool, Error> {
+        let mut ready = false;
+        let mut shown = String::new();
+        let mut sent = String::new();
+        loop {
+            let message = self.lobby_message(ready);
+            if message != shown {
+                renderer.show_message(&message)?;
+                shown = message;
+            }
+            let ready_clients = self.clients.iter().filter(|client| client.ready).count();
+            let status = format!(
+                "lobby {} {}\n",
+                self.players(),
+                ready_clients + ready as usize
+            );
+            if status != sent {
+                self.broadcast(&status);
+                sent = status;
+            }
+
+            if ready && !self.clients.is_empty() && ready_clients == self.clients.len() {
+                return Ok(true);
+            }
+            match input.poll_key(POLL_INTERVAL)? {
+                Some(false) => return Ok(false),
+                Some(true) => ready = true,
+                None => (),
+            }
+            while let Ok(event) = self.events.try_recv() {
+                self.handle(event, None);
+            }
+        }
+    }
+
+    /// Closes the lobby and sends every client its player number and the
+    /// game's configuration, which must be for [`Server::players`] players.
+    pub fn start(&mut self, config: &GameConfig) {
+        self.started.store(true, Ordering::Relaxed);
+        let start = start_message(&Recording {
+            config: config.clone(),
+            ticks: 0,
+            inputs: vec![],
+            removals: vec![],
+        });
+        for (index, client) in self.clients.iter_mut().enumerate() {
+            client.send(&format!("player {}\n{start}", index + 1));
+        }
+        for spectator in &mut self.spectators {
+            spectator.send(&start);
+        }
+    }
+
+    /// `game` as far as it has been sent to the clients.
+    fn recording(&self, game: &Game) -> Recording {
+        let mut recording = Recording::from_game(game);
+        recording.ticks = self.sent;
+        recording.removals.retain(|removal| removal.0 < self.sent);
+        recording
+    }
+
+    /// Sends the changes of every step `game` took since the last call.
+    fn sync(&mut self, game: &Game) {
+        let sent = self.sent;
+        let removals = &game.removals()[game.removals().partition_point(|r| r.0 < sent)..];
+        let inputs = &game.inputs()[game.inputs().partition_point(|i| i.0 < sent)..];
+
+        let mut text = String::new();
+        for tick in sent..game.tick() {
+            for (_, player) in removals.iter().filter(|r| r.0 == tick) {
+                text += &format!("{tick} {player} {LEAVE}\n");
+            }
+            for (_, player, direction) in inputs.iter().filter(|i| i.0 == tick) {
+                text += &format!("{tick} {player} {direction}\n");
+            }
+            text += &format!("step {tick}\n");
+        }
+        self.sent = game.tick();
+        if !text.is_empty() {
+            self.broadcast(&text);
+        }
+    }
+
+    /// Plays `game` like [`run`], with `input` steering the host's snakes
+    /// and the clients the others.
+    pub fn play(
+        &mut self,
+        game: &mut Game,
+        renderer: &mut impl Renderer,
+        input: &mut impl InputSource,
+    ) -> Result<Outcome, Error> {
+        let outcome = run(
+            game,
+            renderer,
+            &mut HostInput {
+                server: self,
+                inner: input,
+                queue: VecDeque::new(),
+            },
+            PAUSE_MENU_NO_RESTART,
+        )?;
+        self.sync(game);
+        self.broadcast("end\n");
+        Ok(outcome)
+    }
+}
+
+/// Merges the host's input with the clients' while sending every step to
+/// the clients.
+///
+/// Turns are held back until the step is due, so that players who press
+/// keys cannot speed up the game for everyone.
+struct HostInput<'a, I: InputSource> {
+    server: &'a mut Server,
+    inner: &'a mut I,
+    queue: VecDeque<Input>,
+}
+
+impl<I: InputSource> InputSource for HostInput<'_, I> {
+    fn poll(&mut self, game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        if let Some(input) = self.queue.pop_front() {
+            return Ok(Some(input));
+        }
+        self.server.sync(game);
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            while let Ok(event) = self.server.events.try_recv() {
+                self.queue.extend(self.server.handle(event, Some(game)));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.inner.poll(game, remaining.min(POLL_INTERVAL))? {
+                Some(input @ (Input::Pause | Input::Resize | Input::Quit)) => {
+                    return Ok(Some(input))
+                }
+                Some(input) => self.queue.push_back(input),
+                None if remaining.is_zero() => return Ok(self.queue.pop_front()),
+                None => (),
+            }
+        }
+    }
+
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        self.inner.wait_for_menu()
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        self.inner.wait_for_key()
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        self.inner.poll_key(timeout)
+    }
+}
+
+/// A connection to a [`Server`], through which a player takes part in its
+/// game or a spectator watches it.
+pub struct Connection {
+    address: String,
+    stream: TcpStream,
+    lines: Receiver<String>,
+    /// Index of the player this connection steers, `None` for spectators.
+    player: Option<usize>,
+    spectator: bool,
+}
+
+impl Connection {
+    /// Connects to the server at `address`, e.g. `localhost:4000`, to play.
+    pub fn join(address: &str) -> Result<Connection, String> {
+        Self::connect(address, false)
+    }
+
+    /// Connects to the server at `address` to watch its game.
+    pub fn spectate(address: &str) -> Result<Connection, String> {
+        Self::connect(address, true)
+    }
+
+    fn connect(address: &str, spectator: bool) -> Result<Connection, String> {
+        let mut stream =
+            TcpStream::connect(address).map_err(|e| format!("cannot connect to {address}: {e}"))?;
+        let _ = stream.set_nodelay(true);
+        // a host that refuses us has already said why, so read that on failure
+        let role = if spectator { "spectate" } else { "join" };
+        let _ = write!(stream, "{PROTOCOL}\n{role}\n");
+
+        let reader = stream
+            .try_clone()
+            .map_err(|e| format!("cannot read from {address}: {e}"))?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut connection = Connection {
+            address: address.to_string(),
+            stream,
+            lines,
+            player: None,
+            spectator,
+        };
+        match connection.next(TIMEOUT)? {
+            Some(line) if line == PROTOCOL => Ok(connection),
+            _ => Err(format!("{address} is not hosting a snake game")),
+        }
+    }
+
+    /// Index of the player this connection steers, `None` for spectators.
+    pub fn player(&self) -> Option<usize> {
+        self.player
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stream, "{line}").map_err(|e| format!("lost the connection to the host: {e}"))
+    }
+
+    /// Waits at most `timeout` for the next line from the server.
+    fn next(&mut self, timeout: Duration) -> Result<Option<String>, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => match line.strip_prefix("error ") {
+                Some(message) => Err(format!("the host refused: {message}")),
+                None => Ok(Some(line)),
+            },
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err("the host closed the connection".to_string())
+            }
+        }
+    }
+
+    fn invalid(line: &str) -> String {
+        format!("invalid message from the host: {line}")
+    }
+
+    /// Shows the lobby until the host starts the game, returning the game so
+    /// far, or `None` if the player left.
+    pub fn lobby(
+        &mut self,
+        renderer: &mut impl Renderer,
+        input: &mut impl InputSource,
+    ) -> Result<Option<Recording>, Error> {
+        let mut ready = false;
+        let mut status = String::new();
+        let mut shown = None;
+        loop {
+            let (greeting, prompt) = match (self.spectator, ready) {
+                (true, _) => (
+                    "Watching",
+                    "Waiting for the game to start, Ctrl+C to leave.",
+                ),
+                (false, false) => (
+                    "Connected to",
+                    "Press any key when you are ready, Ctrl+C to leave.",
+                ),
+                (false, true) => ("Connected to", "Waiting for the other players to be ready."),
+            };
+            let message = format!("{greeting} {}.\n\n{status}\n\n{prompt}", self.address);
+            if shown.as_ref() != Some(&message) {
+                renderer.show_message(&message)?;
+                shown = Some(message);
+            }
+
+            match input.poll_key(POLL_INTERVAL)? {
+                Some(false) => return Ok(None),
+                Some(true) if !ready && !self.spectator => {
+                    self.send("ready")?;
+                    ready = true;
+                }
+                _ => (),
+            }
+
+            while let Some(line) = self.next(Duration::ZERO)? {
+                let words: Vec<&str> = line.split(' ').collect();
+                match words[..] {
+                    ["lobby", players, ready] => {
+                        status = format!("Players: {players}, ready: {ready}");
+                    }
+                    ["player", player] => {
+                        self.player = Some(player.parse().map_err(|_| Self::invalid(&line))?);
+                    }
+                    ["start", count] => {
+                        let count: usize = count.parse().map_err(|_| Self::invalid(&line))?;
+                        let mut text = String::new();
+                        for _ in 0..count {
+                            let line = self
+                                .next(TIMEOUT)?
+                                .ok_or_else(|| "the host stopped answering".to_string())?;
+                            text += &line;
+                            text.push('\n');
+                        }
+                        let recording = Recording::parse(&text)
+                            .map_err(|e| format!("invalid game from the host: {e}"))?;
+                        return Ok(Some(recording));
+                    }
+                    _ => return Err(Self::invalid(&line).into()),
+                }
+            }
+        }
+    }
+
+    /// Follows the game the host started with `recording` until it is over,
+    /// returning it, or `None` if the player left.
+    ///
+    /// Players' turns are sent to the host, which decides when they take
+    /// effect; the local copy of the game only advances when the host says
+    /// so. Spectators switch the highlighted snake with the movement keys.
+    pub fn play(
+        &mut self,
+        recording: &Recording,
+        renderer: &mut impl Renderer,
+        input: &mut impl InputSource,
+    ) -> Result<Option<Game>, Error> {
+        let mut game = recording.game();
+        let players = recording.config.players;
+        let mut highlight = self.player.unwrap_or(0);
+        let mut turns = vec![None; players];
+        let mut removals = vec![];
+        renderer.highlight(Some(highlight));
+        renderer.draw(&game)?;
+
+        loop {
+            match input.poll(&game, POLL_INTERVAL)? {
+                Some(Input::Quit) => return Ok(None),
+                Some(Input::Turn(_, direction)) if self.spectator => {
+                    highlight = match direction {
+                        Direction::Right | Direction::Down => (highlight + 1) % players,
+                        Direction::Left | Direction::Up => (highlight + players - 1) % players,
+                    };
+                    renderer.highlight(Some(highlight));
+                    renderer.draw(&game)?;
+                }
+                Some(Input::Turn(_, direction)) => self.send(&format!("turn {direction}"))?,
+                Some(Input::Resize) => renderer.draw(&game)?,
+                _ => (),
+            }
+
+            while let Some(line) = self.next(Duration::ZERO)? {
+                let words: Vec<&str> = line.split(' ').collect();
+                match words[..] {
+                    ["end"] => return Ok(Some(game)),
+                    ["step", tick] if tick.parse() == Ok(game.tick()) => {
+                        for player in removals.drain(..) {
+                            game.remove(player);
+                        }
+                        game.step(&turns);
+                        turns = vec![None; players];
+                        renderer.draw(&game)?;
+                    }
+                    [_, player, LEAVE] => {
+                        removals.push(player.parse().map_err(|_| Self::invalid(&line))?);
+                    }
+                    [_, player, direction] => {
+                        let player: usize = player.parse().map_err(|_| Self::invalid(&line))?;
+                        let turn = turns.get_mut(player).ok_or_else(|| Self::invalid(&line))?;
+                        *turn = Some(direction.parse().map_err(|_| Self::invalid(&line))?);
+                    }
+                    _ => return Err(Self::invalid(&line).into()),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use std::env;
+use std::path::PathBuf;
+
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .map(|dir| dir.join("clisnake"))
+}
+
+/// Directory for files the game keeps between runs, e.g. high scores.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for files the player edits, e.g. the config file.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
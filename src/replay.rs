@@ -0,0 +1,315 @@
+use crate::cli::{MIN_HEIGHT, MIN_WIDTH};
+use crate::error::Error;
+use crate::frontend::{Input, InputSource, MenuInput};
+use crate::game::{Direction, Game, GameConfig};
+use crate::level::Level;
+use crate::net::MAX_PLAYERS;
+use std::fs;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "snake-recording 3";
+
+/// Written instead of a direction when a player left the game.
+pub const LEAVE: &str = "leave";
+
+/// Everything needed to play a game again exactly as it happened.
+pub struct Recording {
+    pub config: GameConfig,
+    /// Number of steps the game lasted.
+    pub ticks: u64,
+    /// Inputs as `(tick, player, direction)`.
+    pub inputs: Vec<(u64, usize, Direction)>,
+    /// Snakes taken off the board as `(tick, player)`.
+    pub removals: Vec<(u64, usize)>,
+}
+
+fn parse<T: FromStr>(line: usize, key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("line {line}: invalid value for {key}: {value}"))
+}
+
+impl Recording {
+    pub fn from_game(game: &Game) -> Recording {
+        Recording {
+            config: game.config().clone(),
+            ticks: game.tick(),
+            inputs: game.inputs().to_vec(),
+            removals: game.removals().to_vec(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("cannot write {path}: {e}"))
+    }
+
+    /// The recording in the format written by [`Recording::save`].
+    pub fn to_text(&self) -> String {
+        let config = &self.config;
+        let mut out = format!(
+            "{HEADER}\nwidth {}\nheight {}\nspeed_ns {}\ncurve {}\nwrap {}\nseed {}\nplayers {}\nticks {}\n",
+            config.width,
+            config.height,
+            config.speed.as_nanos(),
+            config.curve,
+            config.wrap,
+            config.seed,
+            config.players,
+            self.ticks
+        );
+        if let Some(level) = &config.level {
+            let source = level.source.trim_end();
+            out += &format!("level {}\n{source}\n", source.lines().count());
+        }
+        out.push('\n');
+        for (tick, player, direction) in &self.inputs {
+            out += &format!("{tick} {player} {direction}\n");
+        }
+        for (tick, player) in &self.removals {
+            out += &format!("{tick} {player} {LEAVE}\n");
+        }
+        out
+    }
+
+    /// Plays the recording back without a frontend, returning the game as it
+    /// stood after the recorded number of ticks.
+    pub fn game(&self) -> Game {
+        let mut game = Game::new(&self.config);
+        let mut inputs = self.inputs.iter().peekable();
+        let mut removals = self.removals.iter().peekable();
+        for tick in 0..self.ticks {
+            while let Some((_, player)) = removals.next_if(|removal| removal.0 == tick) {
+                game.remove(*player);
+            }
+            let mut turns = vec![None; self.config.players];
+            while let Some((_, player, direction)) = inputs.next_if(|input| input.0 == tick) {
+                if let Some(turn) = turns.get_mut(*player) {
+                    *turn = Some(*direction);
+                }
+            }
+            game.step(&turns);
+        }
+        game
+    }
+
+    pub fn load(path: &str) -> Result<Recording, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+        Self::parse(&text).map_err(|e| format!("{path}: {e}"))
+    }
+
+    /// Reads a recording in the format written by [`Recording::save`].
+    pub fn parse(text: &str) -> Result<Recording, String> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        if !matches!(lines.next(), Some((_, HEADER))) {
+            return Err("not a snake recording".to_string());
+        }
+
+        let mut config = GameConfig::new(0, 0, 0);
+        let mut ticks = 0;
+        while let Some((number, line)) = lines.next() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("line {number}: expected `key value`"))?;
+            match key {
+                "width" => config.width = parse(number, key, value)?,
+                "height" => config.height = parse(number, key, value)?,
+                "speed_ns" => config.speed = Duration::from_nanos(parse(number, key, value)?),
+                "curve" => config.curve = parse(number, key, value)?,
+                "wrap" => config.wrap = parse(number, key, value)?,
+                "seed" => config.seed = parse(number, key, value)?,
+                "players" => config.players = parse(number, key, value)?,
+                "ticks" => ticks = parse(number, key, value)?,
+                "level" => {
+                    let count = parse(number, key, value)?;
+                    let source: Vec<&str> = lines.by_ref().take(count).map(|(_, l)| l).collect();
+                    let level = Level::parse(&source.join("\n"))
+                        .map_err(|e| format!("level embedded at line {number}: {e}"))?;
+                    config.level = Some(level);
+                }
+                _ => return Err(format!("line {number}: unknown key {key}")),
+            }
+        }
+
+        // recordings also come from network hosts, so anything the game
+        // could not be set up with is an error rather than a panic later
+        if config.width < MIN_WIDTH || config.height < MIN_HEIGHT {
+            return Err(format!(
+                "a {}x{} board is too small (at least {MIN_WIDTH}x{MIN_HEIGHT})",
+                config.width, config.height
+            ));
+        }
+        if !(1..=MAX_PLAYERS).contains(&config.players) {
+            return Err(format!(
+                "{} players, expected 1 to {MAX_PLAYERS}",
+                config.players
+            ));
+        }
+        if let Some(level) = &config.level {
+            if (level.width, level.height) != (config.width, config.height) {
+                return Err(format!(
+                    "the {}x{} level does not match the {}x{} board",
+                    level.width, level.height, config.width, config.height
+                ));
+            }
+        }
+
+        let mut inputs = vec![];
+        let mut removals = vec![];
+        for (number, line) in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let (tick, player, direction) = match fields[..] {
+                [tick, player, direction] => (tick, player, direction),
+                _ => return Err(format!("line {number}: expected `tick player direction`")),
+            };
+            let player = parse(number, "player", player)?;
+            if player >= config.players {
+                return Err(format!("line {number}: no player {player}"));
+            }
+            if direction == LEAVE {
+                removals.push((parse(number, "tick", tick)?, player));
+                continue;
+            }
+            inputs.push((
+                parse(number, "tick", tick)?,
+                player,
+                parse(number, "direction", direction)?,
+            ));
+        }
+
+        Ok(Recording {
+            config,
+            ticks,
+            inputs,
+            removals,
+        })
+    }
+}
+
+/// Feeds recorded inputs back to the game loop at the ticks they were made on.
+///
+/// The wrapped source is still read so the player can pause or quit the
+/// replay and the screen can be resized; its turns are ignored. Players leave
+/// on the ticks they left on in the recorded game. Once the recorded number
+/// of ticks has been played, the replay quits.
+pub struct ReplayInput<I: InputSource> {
+    inner: I,
+    ticks: u64,
+    inputs: Vec<(u64, usize, Direction)>,
+    next: usize,
+    removals: Vec<(u64, usize)>,
+    next_removal: usize,
+}
+
+impl<I: InputSource> ReplayInput<I> {
+    pub fn new(recording: &Recording, inner: I) -> ReplayInput<I> {
+        ReplayInput {
+            inner,
+            ticks: recording.ticks,
+            inputs: recording.inputs.clone(),
+            next: 0,
+            removals: recording.removals.clone(),
+            next_removal: 0,
+        }
+    }
+}
+
+impl<I: InputSource> InputSource for ReplayInput<I> {
+    fn poll(&mut self, game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        if game.tick() >= self.ticks {
+            return Ok(Some(Input::Quit));
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            if let Some(input @ (Input::Pause | Input::Resize | Input::Quit)) =
+                self.inner.poll(game, deadline - now)?
+            {
+                return Ok(Some(input));
+            }
+        }
+
+        if let Some(&(tick, player)) = self.removals.get(self.next_removal) {
+            if tick == game.tick() {
+                self.next_removal += 1;
+                return Ok(Some(Input::Leave(player)));
+            }
+        }
+        match self.inputs.get(self.next) {
+            Some(&(tick, player, direction)) if tick == game.tick() => {
+                self.next += 1;
+                Ok(Some(Input::Turn(player, direction)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        self.inner.wait_for_menu()
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        self.inner.wait_for_key()
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        self.inner.poll_key(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut two_player = GameConfig::new(20, 10, 7);
+        two_player.players = 2;
+        two_player.wrap = true;
+        let level = Level::load("pillars").unwrap();
+        let mut on_level = GameConfig::new(level.width, level.height, 3);
+        on_level.level = Some(level);
+
+        for config in [two_player, on_level] {
+            let mut game = Game::new(&config);
+            let turns = [Direction::Down, Direction::Left, Direction::Up];
+            for (tick, turn) in turns.into_iter().cycle().take(12).enumerate() {
+                let mut inputs = vec![None; config.players];
+                inputs[tick % config.players] = Some(turn);
+                game.step(&inputs);
+            }
+            if config.players > 1 {
+                game.remove(1);
+                game.step(&[None, None]);
+            }
+
+            let text = Recording::from_game(&game).to_text();
+            let recording = Recording::parse(&text).unwrap();
+            assert_eq!(recording.to_text(), text);
+            let replayed = recording.game();
+            assert_eq!(replayed.tick(), game.tick());
+            for (snake, original) in replayed.snakes().iter().zip(game.snakes()) {
+                assert!(snake.body() == original.body());
+                assert!(snake.death() == original.death());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_speed_factors_outside_the_curve_range() {
+        for factor in ["-1", "0", "1.5", "NaN", "inf"] {
+            let text = format!(
+                "{HEADER}\nwidth 30\nheight 20\nspeed_ns 1000\ncurve exponential {factor}\n\n"
+            );
+            assert!(Recording::parse(&text).is_err(), "factor {factor}");
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::game::{Game, GameConfig};
+use crate::paths;
+use std::cmp::Reverse;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "snake-scores 1";
+
+/// Number of entries kept in every table.
+pub const TABLE_SIZE: usize = 10;
+
+/// One finished game in the high-score table.
+#[derive(Clone)]
+pub struct Entry {
+    pub mode: String,
+    pub width: usize,
+    pub height: usize,
+    pub name: String,
+    pub score: usize,
+    pub length: usize,
+    pub duration: Duration,
+    /// Day the game was played, as `YYYY-MM-DD`.
+    pub date: String,
+}
+
+impl Entry {
+    pub fn new(name: &str, game: &Game, duration: Duration) -> Entry {
+        let config = game.config();
+        Entry {
+            mode: config.mode().replace(['\t', '\n'], " "),
+            width: config.width,
+            height: config.height,
+            name: name.replace(['\t', '\n'], " "),
+            score: game.score(),
+            length: game.length(),
+            duration,
+            date: today(),
+        }
+    }
+
+    fn same_table(&self, other: &Entry) -> bool {
+        self.mode == other.mode && self.width == other.width && self.height == other.height
+    }
+}
+
+/// Converts days since the Unix epoch to a `YYYY-MM-DD` date.
+fn date(days: i64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    date((seconds / 86400) as i64)
+}
+
+fn parse<T: FromStr>(line: usize, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("line {line}: invalid value {value}"))
+}
+
+/// All high-score tables, one per mode and board size.
+#[derive(Default)]
+pub struct Scores {
+    entries: Vec<Entry>,
+}
+
+impl Scores {
+    pub fn path() -> Option<PathBuf> {
+        paths::data_dir().map(|dir| dir.join("scores"))
+    }
+
+    /// Reads the saved scores; a missing file means no scores yet.
+    pub fn load() -> Result<Scores, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Scores::default());
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Ok(Scores::default());
+        };
+        Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Scores, String> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        match lines.next() {
+            Some((_, HEADER)) => (),
+            Some((_, header)) if header.starts_with("snake-scores ") => {
+                return Err(format!("unsupported version: {header}"))
+            }
+            _ => return Err("not a score file".to_string()),
+        }
+
+        let mut entries = vec![];
+        for (number, line) in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [mode, width, height, name, score, length, duration, date] = fields[..] else {
+                return Err(format!("line {number}: expected 8 fields"));
+            };
+            entries.push(Entry {
+                mode: mode.to_string(),
+                width: parse(number, width)?,
+                height: parse(number, height)?,
+                name: name.to_string(),
+                score: parse(number, score)?,
+                length: parse(number, length)?,
+                duration: Duration::from_millis(parse(number, duration)?),
+                date: date.to_string(),
+            });
+        }
+
+        Ok(Scores { entries })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("cannot find a data directory, HOME is not set")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {e}", dir.display()))?;
+        }
+
+        let mut out = format!("{HEADER}\n");
+        for entry in &self.entries {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                entry.mode,
+                entry.width,
+                entry.height,
+                entry.name,
+                entry.score,
+                entry.length,
+                entry.duration.as_millis(),
+                entry.date
+            )
+            .unwrap();
+        }
+
+        fs::write(&path, out).map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+
+    /// Adds `entry` to its table, returning its rank if it made it into the top entries.
+    pub fn add(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self
+            .table(&entry)
+            .iter()
+            .take_while(|other| other.score >= entry.score)
+            .count();
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+
+        self.entries.push(entry.clone());
+        self.entries.sort_by_key(|other| Reverse(other.score));
+
+        let mut kept = 0;
+        self.entries.retain(|other| {
+            if !other.same_table(&entry) {
+                return true;
+            }
+            kept += 1;
+            kept <= TABLE_SIZE
+        });
+
+        Some(rank)
+    }
+
+    /// Best score in the table games played with `config` go into.
+    pub fn best(&self, config: &GameConfig) -> Option<usize> {
+        let mode = config.mode().replace(['\t', '\n'], " ");
+        self.entries
+            .iter()
+            .filter(|e| e.mode == mode && e.width == config.width && e.height == config.height)
+            .map(|e| e.score)
+            .max()
+    }
+
+    /// Entries comparable to `entry`, best first.
+    fn table(&self, entry: &Entry) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|e| e.same_table(entry))
+            .collect()
+    }
+
+    /// Table of all entries comparable to `entry`, ready to be printed.
+    pub fn format_table(&self, entry: &Entry) -> String {
+        let mut out = format!(
+            "{} {}x{}\n{:>3}  {:<15} {:>6} {:>7} {:>9}  date\n",
+            entry.mode, entry.width, entry.height, "#", "name", "score", "length", "time"
+        );
+        for (rank, entry) in self.table(entry).iter().enumerate() {
+            let seconds = entry.duration.as_secs();
+            writeln!(
+                out,
+                "{:>3}  {:<15.15} {:>6} {:>7} {:>6}:{:02}  {}",
+                rank + 1,
+                entry.name,
+                entry.score,
+                entry.length,
+                seconds / 60,
+                seconds % 60,
+                entry.date
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    /// Every table, ready to be printed.
+    pub fn format_all(&self) -> String {
+        let mut tables: Vec<&Entry> = vec![];
+        for entry in &self.entries {
+            if !tables.iter().any(|table| table.same_table(entry)) {
+                tables.push(entry);
+            }
+        }
+        tables.sort_by_key(|entry| (entry.mode.clone(), entry.width, entry.height));
+
+        if tables.is_empty() {
+            return "No high scores yet.".to_string();
+        }
+        tables
+            .iter()
+            .map(|entry| self.format_table(entry))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,140 @@
+use crate::game::GameConfig;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How the time between two steps changes over a game.
+///
+/// Curves are written as text like `exponential 0.9997`, the same way on the
+/// command line, in the config file and in recordings.
+#[derive(Clone, PartialEq)]
+pub enum SpeedCurve {
+    /// The step time never changes.
+    Constant,
+    /// The step time is multiplied by the factor, in (0, 1], after every step.
+    Exponential(f64),
+    /// Every time a snake eats, `step` comes off the step time, down to `min`.
+    PerFood { step: Duration, min: Duration },
+    /// The step time once the longest snake reaches a length, as
+    /// `(length, time)` by increasing length; shorter snakes keep the
+    /// initial step time.
+    Table(Vec<(usize, Duration)>),
+}
+
+impl SpeedCurve {
+    /// Step time after a step that left the step time at `current`, where
+    /// `ate` tells whether a snake ate and `length` is the longest snake's
+    /// length.
+    pub fn next(&self, initial: Duration, current: Duration, ate: bool, length: usize) -> Duration {
+        match self {
+            SpeedCurve::Constant => current,
+            SpeedCurve::Exponential(factor) => current.mul_f64(*factor),
+            SpeedCurve::PerFood { step, min } if ate => current.saturating_sub(*step).max(*min),
+            SpeedCurve::PerFood { .. } => current,
+            SpeedCurve::Table(table) => table
+                .iter()
+                .take_while(|(from, _)| *from <= length)
+                .last()
+                .map_or(initial, |(_, time)| *time),
+        }
+    }
+}
+
+impl fmt::Display for SpeedCurve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpeedCurve::Constant => write!(f, "constant"),
+            SpeedCurve::Exponential(factor) => write!(f, "exponential {factor}"),
+            SpeedCurve::PerFood { step, min } => {
+                write!(f, "per-food {} {}", step.as_millis(), min.as_millis())
+            }
+            SpeedCurve::Table(table) => {
+                write!(f, "table")?;
+                for (length, time) in table {
+                    write!(f, " {length}:{}", time.as_millis())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn millis(value: &str) -> Result<Duration, String> {
+    match value.parse() {
+        Ok(ms) if ms > 0 => Ok(Duration::from_millis(ms)),
+        _ => Err(format!("invalid time in ms: {value}")),
+    }
+}
+
+impl FromStr for SpeedCurve {
+    type Err = String;
+
+    /// Parses `constant`, `exponential <factor>`, `per-food <ms> <min ms>`
+    /// or `table <length>:<ms>...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let curve = match (words.next(), words.collect::<Vec<_>>().as_slice()) {
+            (Some("constant"), []) => SpeedCurve::Constant,
+            (Some("exponential"), [factor]) => match factor.parse() {
+                Ok(factor) if factor > 0. && factor <= 1. => SpeedCurve::Exponential(factor),
+                _ => return Err(format!("the factor must be in (0, 1]: {factor}")),
+            },
+            (Some("per-food"), [step, min]) => SpeedCurve::PerFood {
+                step: millis(step)?,
+                min: millis(min)?,
+            },
+            (Some("table"), entries) if !entries.is_empty() => {
+                let table = entries
+                    .iter()
+                    .map(|entry| {
+                        let (length, time) = entry
+                            .split_once(':')
+                            .ok_or_else(|| format!("expected <length>:<ms>: {entry}"))?;
+                        let length = length
+                            .parse()
+                            .map_err(|_| format!("invalid length: {length}"))?;
+                        Ok((length, millis(time)?))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                if !table.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+                    return Err("table lengths must increase".to_string());
+                }
+                SpeedCurve::Table(table)
+            }
+            _ => {
+                return Err(format!(
+                    "unknown speed curve {s}, expected constant, exponential <factor>, \
+                     per-food <ms> <min ms> or table <length>:<ms>..."
+                ))
+            }
+        };
+        Ok(curve)
+    }
+}
+
+/// Names of the difficulty presets, easiest first.
+pub const DIFFICULTIES: [&str; 4] = ["easy", "normal", "hard", "insane"];
+
+/// Initial step time and speed curve of the difficulty preset called `name`.
+pub fn difficulty(name: &str) -> Result<(Duration, SpeedCurve), String> {
+    let ms = Duration::from_millis;
+    match name {
+        "easy" => Ok((ms(350), SpeedCurve::Constant)),
+        "normal" => Ok((GameConfig::DEFAULT_SPEED, GameConfig::DEFAULT_CURVE)),
+        "hard" => Ok((
+            ms(200),
+            SpeedCurve::PerFood {
+                step: ms(5),
+                min: ms(70),
+            },
+        )),
+        "insane" => Ok((
+            ms(100),
+            SpeedCurve::Table(vec![(10, ms(80)), (20, ms(65)), (40, ms(50))]),
+        )),
+        _ => Err(format!(
+            "unknown difficulty {name}, choose from {}",
+            DIFFICULTIES.join(", ")
+        )),
+    }
+}
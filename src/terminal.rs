@@ -0,0 +1,624 @@
+use crate::error::Error;
+use crate::frontend::{Input, InputSource, MenuInput, MenuItem, Renderer};
+use crate::game::{Block, Direction, Game};
+use crate::keys::Keys;
+use crate::theme::{paint, Border, Cell, Theme, THEMES};
+use crossterm::cursor;
+use crossterm::event;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::style::{Color, Print};
+use crossterm::terminal;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm::{execute, queue};
+use std::io::stdout;
+use std::io::Write;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+/// Largest field that fits into the current terminal with a line of HUD
+/// above it, as `(width, height)`.
+pub fn field_size() -> Result<(usize, usize), Error> {
+    let (term_width, term_height) = terminal::size()?;
+    Ok((
+        (term_width as usize / 2).saturating_sub(2),
+        (term_height as usize).saturating_sub(3),
+    ))
+}
+
+/// Whether a [`TerminalGuard`] has the terminal set up for the game.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Puts the terminal back the way the shell expects it, unless that
+/// already happened.
+fn reset() -> Result<(), Error> {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    disable_raw_mode()?;
+    execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    Ok(())
+}
+
+/// Keeps the terminal in raw mode with a hidden cursor while it lives, and
+/// draws on the alternate screen so the shell's screen and scrollback look
+/// the same afterwards.
+///
+/// The terminal is reset when the guard is dropped, also while unwinding
+/// from a panic, and by a panic hook before the panic message is printed,
+/// so that the message stays readable.
+pub struct TerminalGuard(());
+
+impl TerminalGuard {
+    pub fn new() -> Result<TerminalGuard, Error> {
+        static HOOK: Once = Once::new();
+        HOOK.call_once(|| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                let _ = reset();
+                previous(info);
+            }));
+        });
+
+        ACTIVE.store(true, Ordering::SeqCst);
+        let guard = TerminalGuard(());
+        enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+
+    /// Resets the terminal before the guard is dropped, to report errors.
+    pub fn restore(&mut self) -> Result<(), Error> {
+        reset()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = reset();
+    }
+}
+
+/// Columns the HUD panel next to the board takes up, the gap to the board
+/// included.
+const PANEL_WIDTH: usize = 26;
+
+/// Where the HUD goes, depending on the room around the board.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Hud {
+    /// One line per value, right of the board.
+    Side,
+    /// A single line above the board.
+    Top,
+    /// Only the scores, set into the bottom border if they fit there.
+    Border,
+}
+
+/// Where the board and the HUD go in the terminal.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Layout {
+    /// Top left corner of the board's border.
+    origin: (u16, u16),
+    /// Columns and rows of the board, border included.
+    board: (usize, usize),
+    hud: Hud,
+}
+
+impl Layout {
+    /// Centers `game` and its HUD in the terminal, `None` if the board does
+    /// not fit.
+    fn new(game: &Game) -> Result<Option<Layout>, Error> {
+        let (columns, rows) = terminal::size()?;
+        let (columns, rows) = (columns as usize, rows as usize);
+        let board = board_size(game);
+        let (width, height) = board;
+        if width > columns || height > rows {
+            return Ok(None);
+        }
+
+        let (hud, used_width, used_height) = if width + PANEL_WIDTH <= columns {
+            (Hud::Side, width + PANEL_WIDTH, height)
+        } else if height < rows {
+            (Hud::Top, width, height + 1)
+        } else {
+            (Hud::Border, width, height)
+        };
+        let left = (columns - used_width) / 2;
+        let top = (rows - used_height) / 2 + usize::from(hud == Hud::Top);
+        Ok(Some(Layout {
+            origin: (left as u16, top as u16),
+            board,
+            hud,
+        }))
+    }
+
+    /// Columns a HUD line takes up.
+    fn hud_width(&self) -> usize {
+        match self.hud {
+            Hud::Side => PANEL_WIDTH - 2,
+            Hud::Top | Hud::Border => self.board.0,
+        }
+    }
+
+    /// Where the HUD's `line` starts.
+    fn hud_position(&self, line: usize) -> (u16, u16) {
+        let (left, top) = self.origin;
+        match self.hud {
+            Hud::Side => (left + self.board.0 as u16 + 2, top + line as u16),
+            Hud::Top | Hud::Border => (left, top - 1),
+        }
+    }
+}
+
+/// Columns and rows `game` takes up on screen, border included.
+fn board_size(game: &Game) -> (usize, usize) {
+    let field = game.field();
+    (field.width() * 2 + 2, field.height() + 2)
+}
+
+/// `text` cut or padded with spaces to `width` columns.
+fn fit(text: &str, width: usize) -> String {
+    format!("{text:<width$.width$}")
+}
+
+/// What was last drawn, so the next frame only has to redraw what changed.
+struct Frame {
+    rows: Vec<Vec<Block>>,
+    wraps: bool,
+    layout: Layout,
+    bottom: String,
+    hud: Vec<String>,
+    /// Whether the pause menu was drawn over the board since.
+    menu: bool,
+}
+
+/// Renders the game to stdout using crossterm.
+///
+/// The board is centered in the terminal, with a HUD next to or above it
+/// as room allows. The terminal is set up while the renderer lives, see
+/// [`TerminalGuard`].
+pub struct TerminalRenderer {
+    guard: TerminalGuard,
+    theme: &'static Theme,
+    /// Whether to draw in the theme's colors.
+    color: bool,
+    highlight: Option<usize>,
+    /// Best score so far on the board being played, shown in the HUD.
+    best: Option<usize>,
+    /// Frame currently on screen, `None` if it has to be drawn from scratch.
+    frame: Option<Frame>,
+}
+
+impl TerminalRenderer {
+    /// Draws with `theme`, in monochrome unless `color` is set.
+    pub fn new(theme: &'static Theme, color: bool) -> Result<TerminalRenderer, Error> {
+        Ok(TerminalRenderer {
+            guard: TerminalGuard::new()?,
+            theme,
+            color,
+            highlight: None,
+            best: None,
+            frame: None,
+        })
+    }
+
+    /// Shows `best` as the high score to beat in the HUD.
+    pub fn set_best(&mut self, best: Option<usize>) {
+        self.best = best;
+    }
+
+    fn cell(&self, block: Block) -> Cell {
+        self.theme.cell(block, self.color)
+    }
+
+    /// `text` in `color`, unless colors are off.
+    fn paint(&self, text: &str, color: Option<Color>) -> String {
+        paint(text, color.filter(|_| self.color))
+    }
+
+    fn draw_field(&mut self, game: &Game) -> Result<(), Error> {
+        let Some(layout) = Layout::new(game)? else {
+            let (columns, rows) = terminal::size()?;
+            let (width, height) = board_size(game);
+            return self.show_message(&format!(
+                "Terminal too small\n\nThe board needs {width}x{height} characters but the terminal has {columns}x{rows}.\nEnlarge it to go on."
+            ));
+        };
+        let field = game.field();
+        let border = self.theme.border(field.wraps());
+        let horizontal = border.horizontal.to_string();
+        let vertical = self.paint(&border.vertical.to_string(), self.theme.border_color);
+        let rows = field.rows();
+        let bottom = self.bottom_border(game, border, layout.hud);
+        let hud = self.hud(game, &layout);
+        let (left, top) = layout.origin;
+        let mut out = stdout().lock();
+
+        let previous = self.frame.take().filter(|frame| {
+            frame.layout == layout
+                && frame.wraps == field.wraps()
+                && frame.rows.len() == rows.len()
+                && frame.rows[0].len() == rows[0].len()
+                && !frame.menu
+        });
+        match &previous {
+            Some(frame) => {
+                for (y, (row, old)) in rows.iter().zip(&frame.rows).enumerate() {
+                    for (x, block) in row.iter().enumerate() {
+                        if *block != old[x] {
+                            queue!(
+                                out,
+                                cursor::MoveTo(left + 1 + 2 * x as u16, top + 1 + y as u16),
+                                Print(self.cell(*block))
+                            )?;
+                        }
+                    }
+                }
+                if bottom != frame.bottom {
+                    queue!(
+                        out,
+                        cursor::MoveTo(left, top + 1 + rows.len() as u16),
+                        Print(&bottom)
+                    )?;
+                }
+            }
+            None => {
+                queue!(
+                    out,
+                    terminal::Clear(terminal::ClearType::All),
+                    cursor::MoveTo(left, top),
+                    Print(self.paint(
+                        &format!(
+                            "{}{}{}",
+                            border.top_left,
+                            horizontal.repeat(field.width() * 2),
+                            border.top_right
+                        ),
+                        self.theme.border_color
+                    ))
+                )?;
+                for (y, row) in rows.iter().enumerate() {
+                    queue!(
+                        out,
+                        cursor::MoveTo(left, top + 1 + y as u16),
+                        Print(&vertical)
+                    )?;
+                    for block in row {
+                        queue!(out, Print(self.cell(*block)))?;
+                    }
+                    queue!(out, Print(&vertical))?;
+                }
+                queue!(
+                    out,
+                    cursor::MoveTo(left, top + 1 + rows.len() as u16),
+                    Print(&bottom)
+                )?;
+            }
+        }
+        for (index, line) in hud.iter().enumerate() {
+            if previous.as_ref().and_then(|frame| frame.hud.get(index)) != Some(line) {
+                let (x, y) = layout.hud_position(index);
+                queue!(out, cursor::MoveTo(x, y), Print(line))?;
+            }
+        }
+        out.flush()?;
+
+        self.frame = Some(Frame {
+            rows: rows.to_vec(),
+            wraps: field.wraps(),
+            layout,
+            bottom,
+            hud,
+            menu: false,
+        });
+        Ok(())
+    }
+
+    /// Label, value and color of everything the HUD shows, most important
+    /// first.
+    fn hud_items(&self, game: &Game) -> Vec<(String, String, Option<Color>)> {
+        let mut items = vec![];
+        match game.snakes() {
+            [snake] => {
+                items.push(("Score".to_string(), snake.score().to_string(), None));
+                items.push(("Length".to_string(), snake.length().to_string(), None));
+                if let Some(best) = self.best {
+                    items.push(("Best".to_string(), best.to_string(), None));
+                }
+            }
+            snakes => {
+                for (player, snake) in snakes.iter().enumerate() {
+                    let score = snake.score().to_string();
+                    if self.highlight == Some(player) {
+                        let color = self.theme.head_color(player);
+                        items.push((format!("[P{}]", player + 1), score, color));
+                    } else {
+                        items.push((format!("P{}", player + 1), score, None));
+                    }
+                }
+            }
+        }
+        let cycle_time = game.cycle_time();
+        let speed = format!(
+            "{} ms {:.1}/s",
+            cycle_time.as_millis(),
+            1. / cycle_time.as_secs_f64()
+        );
+        items.push(("Speed".to_string(), speed, None));
+        let seconds = game.elapsed().as_secs();
+        let time = format!("{}:{:02}", seconds / 60, seconds % 60);
+        items.push(("Time".to_string(), time, None));
+        items.push(("Mode".to_string(), game.config().mode(), None));
+        items
+    }
+
+    /// Lines of the HUD, each exactly as wide as the layout has room for.
+    fn hud(&self, game: &Game, layout: &Layout) -> Vec<String> {
+        let width = layout.hud_width();
+        let items = self.hud_items(game);
+        match layout.hud {
+            Hud::Side => items
+                .iter()
+                .map(|(label, value, color)| {
+                    self.paint(&fit(&format!("{label:<7}{value}"), width), *color)
+                })
+                .collect(),
+            Hud::Top => {
+                let line = items
+                    .iter()
+                    .map(|(label, value, _)| format!("{label} {value}"))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                vec![fit(&line, width)]
+            }
+            Hud::Border => vec![],
+        }
+    }
+
+    /// Bottom border line, with the scores set into it if the HUD has no
+    /// room of its own and they fit.
+    fn bottom_border(&self, game: &Game, border: &Border, hud: Hud) -> String {
+        let line = |text: String| self.paint(&text, self.theme.border_color);
+        let horizontal = border.horizontal.to_string();
+        let width = game.field().width() * 2;
+        let plain = line(format!(
+            "{}{}{}",
+            border.bottom_left,
+            horizontal.repeat(width),
+            border.bottom_right
+        ));
+        if hud != Hud::Border {
+            return plain;
+        }
+
+        let scores: Vec<(String, Option<Color>)> = match game.snakes() {
+            [snake] => vec![(format!("score: {}", snake.score()), None)],
+            snakes => snakes
+                .iter()
+                .enumerate()
+                .map(|(player, snake)| {
+                    let score = format!("P{}: {}", player + 1, snake.score());
+                    if self.highlight == Some(player) {
+                        (format!("[{score}]"), self.theme.head_color(player))
+                    } else {
+                        (score, None)
+                    }
+                })
+                .collect(),
+        };
+        let score_len =
+            scores.iter().map(|(score, _)| score.len()).sum::<usize>() + 2 * (scores.len() - 1);
+        let Some(rest) = width.checked_sub(score_len + 4) else {
+            return plain;
+        };
+        let scores: Vec<String> = scores
+            .iter()
+            .map(|(score, color)| self.paint(score, *color))
+            .collect();
+        format!(
+            "{} {} {}",
+            line(format!("{}{}", border.bottom_left, horizontal.repeat(2))),
+            scores.join("  "),
+            line(format!(
+                "{}{}",
+                horizontal.repeat(rest),
+                border.bottom_right
+            ))
+        )
+    }
+
+    /// Leaves raw mode and the alternate screen so the shell can be used again.
+    pub fn restore(&mut self) -> Result<(), Error> {
+        self.frame = None;
+        self.guard.restore()
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn draw(&mut self, game: &Game) -> Result<(), Error> {
+        self.draw_field(game)
+    }
+
+    fn fits(&mut self, game: &Game) -> Result<bool, Error> {
+        Ok(Layout::new(game)?.is_some())
+    }
+
+    fn highlight(&mut self, player: Option<usize>) {
+        self.highlight = player;
+    }
+
+    fn show_message(&mut self, message: &str) -> Result<(), Error> {
+        self.frame = None;
+        let mut out = stdout().lock();
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        for line in message.lines() {
+            queue!(out, Print(format!("{line}\r\n")))?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn draw_menu(&mut self, game: &Game, menu: &[MenuItem], selected: usize) -> Result<(), Error> {
+        let Some(layout) = Layout::new(game)? else {
+            return Ok(());
+        };
+        let labels: Vec<String> = menu
+            .iter()
+            .map(|item| match item {
+                MenuItem::Theme => format!("Theme: {}", self.theme.name),
+                item => item.label().to_string(),
+            })
+            .collect();
+        // a border, the mark and a space on either side of the labels
+        let longest = labels.iter().map(|label| label.chars().count()).max();
+        let width = (longest.unwrap_or(0) + 6).min(layout.board.0);
+        let height = menu.len() + 2;
+        let left = layout.origin.0 + ((layout.board.0 - width) / 2) as u16;
+        let top = layout.origin.1 + (layout.board.1.saturating_sub(height) / 2) as u16;
+
+        let border = &self.theme.wall_border;
+        let horizontal = border.horizontal.to_string();
+        let vertical = self.paint(&border.vertical.to_string(), self.theme.border_color);
+        let title = format!("{0}{0} Paused {1}", horizontal, horizontal.repeat(width));
+        let mut out = stdout().lock();
+        queue!(
+            out,
+            cursor::MoveTo(left, top),
+            Print(self.paint(
+                &format!(
+                    "{}{}{}",
+                    border.top_left,
+                    fit(&title, width - 2),
+                    border.top_right
+                ),
+                self.theme.border_color
+            ))
+        )?;
+        for (index, label) in labels.iter().enumerate() {
+            let line = if index == selected {
+                self.paint(
+                    &fit(&format!(" > {label}"), width - 2),
+                    self.theme.head_color(0),
+                )
+            } else {
+                fit(&format!("   {label}"), width - 2)
+            };
+            queue!(
+                out,
+                cursor::MoveTo(left, top + 1 + index as u16),
+                Print(format!("{vertical}{line}{vertical}"))
+            )?;
+        }
+        queue!(
+            out,
+            cursor::MoveTo(left, top + height as u16 - 1),
+            Print(self.paint(
+                &format!(
+                    "{}{}{}",
+                    border.bottom_left,
+                    horizontal.repeat(width - 2),
+                    border.bottom_right
+                ),
+                self.theme.border_color
+            ))
+        )?;
+        out.flush()?;
+
+        if let Some(frame) = &mut self.frame {
+            frame.menu = true;
+        }
+        Ok(())
+    }
+
+    fn next_theme(&mut self) {
+        let index = THEMES
+            .iter()
+            .position(|theme| theme.name == self.theme.name)
+            .unwrap_or(0);
+        self.theme = THEMES[(index + 1) % THEMES.len()];
+        self.frame = None;
+    }
+}
+
+/// Reads key presses from the terminal.
+///
+/// What the keys do is up to the [`Keys`] given. With one player, the keys
+/// of either player steer the snake.
+pub struct KeyboardInput {
+    players: usize,
+    keys: Keys,
+}
+
+impl KeyboardInput {
+    pub fn new(players: usize, keys: Keys) -> KeyboardInput {
+        KeyboardInput { players, keys }
+    }
+
+    /// Input `key` is bound to, if any.
+    fn input(&self, key: KeyEvent) -> Option<Input> {
+        self.keys.input(key, self.players)
+    }
+}
+
+impl InputSource for KeyboardInput {
+    fn poll(&mut self, _game: &Game, timeout: Duration) -> Result<Option<Input>, Error> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        let input = match event::read()? {
+            Event::Key(key) => self.input(key),
+            Event::Resize(..) => Some(Input::Resize),
+            _ => None,
+        };
+        Ok(input)
+    }
+
+    /// Besides the keys bound to pausing and quitting, which close the menu
+    /// and quit, the keys to turn up or down and the arrows move through the
+    /// menu, Enter or space picks an entry, and Esc closes it as well.
+    fn wait_for_menu(&mut self) -> Result<MenuInput, Error> {
+        loop {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::Resize(..) => return Ok(MenuInput::Resize),
+                _ => continue,
+            };
+            let input = match (self.input(key), key.code) {
+                (Some(Input::Quit), _) => MenuInput::Quit,
+                (Some(Input::Pause), _) | (_, KeyCode::Esc) => MenuInput::Close,
+                (Some(Input::Turn(_, Direction::Up)), _) | (_, KeyCode::Up) => MenuInput::Up,
+                (Some(Input::Turn(_, Direction::Down)), _) | (_, KeyCode::Down) => MenuInput::Down,
+                (_, KeyCode::Enter | KeyCode::Char(' ')) => MenuInput::Select,
+                _ => continue,
+            };
+            return Ok(input);
+        }
+    }
+
+    fn wait_for_key(&mut self) -> Result<bool, Error> {
+        loop {
+            if let Event::Key(key) = event::read()? {
+                return Ok(self.input(key) != Some(Input::Quit));
+            }
+        }
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Result<Option<bool>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(remaining)? {
+                return Ok(None);
+            }
+            if let Event::Key(key) = event::read()? {
+                return Ok(Some(self.input(key) != Some(Input::Quit)));
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::game::Block;
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use std::fmt;
+
+/// Characters a border around the field is drawn with.
+pub struct Border {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub vertical: char,
+}
+
+/// Text filling the two columns of one block, and its color.
+#[derive(Clone, Copy)]
+pub struct Cell {
+    pub text: &'static str,
+    /// `None` leaves the terminal's own text color.
+    pub color: Option<Color>,
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&paint(self.text, self.color))
+    }
+}
+
+/// `text` in `color`, if there is one.
+pub fn paint(text: &str, color: Option<Color>) -> String {
+    match color {
+        Some(color) => format!("{}{text}{}", SetForegroundColor(color), ResetColor),
+        None => text.to_string(),
+    }
+}
+
+const fn cell(text: &'static str, color: Color) -> Cell {
+    Cell {
+        text,
+        color: Some(color),
+    }
+}
+
+const fn plain(text: &'static str) -> Cell {
+    Cell { text, color: None }
+}
+
+/// How the field looks in the terminal.
+pub struct Theme {
+    /// Name to pick the theme by, e.g. with `--theme`.
+    pub name: &'static str,
+    pub empty: Cell,
+    pub food: Cell,
+    pub wall: Cell,
+    /// Body and head of each player's snake, repeating for further players.
+    pub snakes: [(Cell, Cell); 4],
+    /// Head drawn without colors, which in some themes are all that tells
+    /// it apart from the body.
+    pub plain_head: &'static str,
+    /// Border the snake dies on.
+    pub wall_border: Border,
+    /// Border the snake passes through on wrapping boards.
+    pub open_border: Border,
+    pub border_color: Option<Color>,
+}
+
+const HEAVY: Border = Border {
+    top_left: '┏',
+    top_right: '┓',
+    bottom_left: '┗',
+    bottom_right: '┛',
+    horizontal: '━',
+    vertical: '┃',
+};
+
+const DASHED: Border = Border {
+    top_left: '┌',
+    top_right: '┐',
+    bottom_left: '└',
+    bottom_right: '┘',
+    horizontal: '╌',
+    vertical: '╎',
+};
+
+/// The original look.
+pub const CLASSIC: Theme = Theme {
+    name: "classic",
+    empty: plain("  "),
+    food: plain("▒▒"),
+    wall: plain("██"),
+    snakes: [
+        (plain("██"), cell("██", Color::Yellow)),
+        (cell("██", Color::Green), cell("██", Color::Cyan)),
+        (cell("██", Color::Magenta), cell("██", Color::Red)),
+        (cell("██", Color::Blue), cell("██", Color::DarkCyan)),
+    ],
+    plain_head: "▓▓",
+    wall_border: HEAVY,
+    open_border: DASHED,
+    border_color: None,
+};
+
+pub const NEON: Theme = Theme {
+    name: "neon",
+    empty: plain("  "),
+    food: cell("▓▓", Color::Magenta),
+    wall: cell("██", Color::DarkMagenta),
+    snakes: [
+        (cell("██", Color::Cyan), cell("██", Color::White)),
+        (cell("██", Color::Green), cell("██", Color::White)),
+        (cell("██", Color::Yellow), cell("██", Color::White)),
+        (cell("██", Color::Red), cell("██", Color::White)),
+    ],
+    plain_head: "▓▓",
+    wall_border: Border {
+        top_left: '╔',
+        top_right: '╗',
+        bottom_left: '╚',
+        bottom_right: '╝',
+        horizontal: '═',
+        vertical: '║',
+    },
+    open_border: Border {
+        top_left: '╭',
+        top_right: '╮',
+        bottom_left: '╰',
+        bottom_right: '╯',
+        horizontal: '┄',
+        vertical: '┆',
+    },
+    border_color: Some(Color::Magenta),
+};
+
+/// Bright colors and heads that differ from bodies in shape, too.
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    empty: plain("  "),
+    food: cell("██", Color::Red),
+    wall: cell("██", Color::Blue),
+    snakes: [
+        (cell("▓▓", Color::White), cell("██", Color::White)),
+        (cell("▓▓", Color::Yellow), cell("██", Color::Yellow)),
+        (cell("▓▓", Color::Cyan), cell("██", Color::Cyan)),
+        (cell("▓▓", Color::Green), cell("██", Color::Green)),
+    ],
+    plain_head: "██",
+    wall_border: HEAVY,
+    open_border: DASHED,
+    border_color: Some(Color::White),
+};
+
+// the Okabe-Ito palette, which stays apart with every common kind of
+// color blindness
+const ORANGE: Color = Color::Rgb {
+    r: 230,
+    g: 159,
+    b: 0,
+};
+const SKY_BLUE: Color = Color::Rgb {
+    r: 86,
+    g: 180,
+    b: 233,
+};
+const BLUISH_GREEN: Color = Color::Rgb {
+    r: 0,
+    g: 158,
+    b: 115,
+};
+const YELLOW: Color = Color::Rgb {
+    r: 240,
+    g: 228,
+    b: 66,
+};
+const BLUE: Color = Color::Rgb {
+    r: 0,
+    g: 114,
+    b: 178,
+};
+const VERMILLION: Color = Color::Rgb {
+    r: 213,
+    g: 94,
+    b: 0,
+};
+const REDDISH_PURPLE: Color = Color::Rgb {
+    r: 204,
+    g: 121,
+    b: 167,
+};
+
+/// Colors that stay distinguishable with color blindness.
+pub const COLORBLIND: Theme = Theme {
+    name: "colorblind",
+    empty: plain("  "),
+    food: cell("▒▒", YELLOW),
+    wall: cell("██", Color::Grey),
+    snakes: [
+        (cell("██", BLUE), cell("██", ORANGE)),
+        (cell("██", SKY_BLUE), cell("██", VERMILLION)),
+        (cell("██", BLUISH_GREEN), cell("██", YELLOW)),
+        (cell("██", REDDISH_PURPLE), cell("██", ORANGE)),
+    ],
+    plain_head: "▓▓",
+    wall_border: HEAVY,
+    open_border: DASHED,
+    border_color: None,
+};
+
+/// Only ASCII characters, for terminals and fonts without box drawing.
+pub const ASCII: Theme = Theme {
+    name: "ascii",
+    empty: plain("  "),
+    food: plain("()"),
+    wall: plain("##"),
+    snakes: [
+        (plain("[]"), cell("@@", Color::Yellow)),
+        (cell("[]", Color::Green), cell("@@", Color::Cyan)),
+        (cell("[]", Color::Magenta), cell("@@", Color::Red)),
+        (cell("[]", Color::Blue), cell("@@", Color::DarkCyan)),
+    ],
+    plain_head: "@@",
+    wall_border: Border {
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+        horizontal: '-',
+        vertical: '|',
+    },
+    open_border: Border {
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+        horizontal: '.',
+        vertical: ':',
+    },
+    border_color: None,
+};
+
+/// Every built-in theme, the default first.
+pub const THEMES: [&Theme; 5] = [&CLASSIC, &NEON, &HIGH_CONTRAST, &COLORBLIND, &ASCII];
+
+/// The built-in theme called `name`.
+pub fn find(name: &str) -> Result<&'static Theme, String> {
+    THEMES
+        .iter()
+        .find(|theme| theme.name == name)
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<&str> = THEMES.iter().map(|theme| theme.name).collect();
+            format!("unknown theme {name}, choose from {}", names.join(", "))
+        })
+}
+
+/// Whether the `NO_COLOR` convention asks to leave colors out,
+/// see <https://no-color.org>.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+impl Theme {
+    /// How `block` looks, leaving out colors unless `color` is set.
+    pub fn cell(&self, block: Block, color: bool) -> Cell {
+        let cell = match block {
+            Block::Empty => self.empty,
+            Block::Food => self.food,
+            Block::Wall => self.wall,
+            Block::Snake(player) => self.snakes[player % self.snakes.len()].0,
+            Block::SnakeHead(_) if !color => plain(self.plain_head),
+            Block::SnakeHead(player) => self.snakes[player % self.snakes.len()].1,
+        };
+        if color {
+            cell
+        } else {
+            plain(cell.text)
+        }
+    }
+
+    /// Color of `player`'s head, which marks their score where it is
+    /// highlighted.
+    pub fn head_color(&self, player: usize) -> Option<Color> {
+        self.snakes[player % self.snakes.len()].1.color
+    }
+
+    /// The border of a field that wraps around or not.
+    pub fn border(&self, wraps: bool) -> &Border {
+        if wraps {
+            &self.open_border
+        } else {
+            &self.wall_border
+        }
+    }
+}